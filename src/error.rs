@@ -6,6 +6,15 @@ quick_error! {
         MapSizeZero {
             description("You cannot create a shared memory mapping of 0 size")
         }
+        OsIdTooLong {
+            description("The os_id of the mapping exceeds the maximum length allowed by the OS")
+        }
+        InvalidOsIdPrefix {
+            description("The prefix for generated os_ids must start with a '/'")
+        }
+        MapSizeTooLarge {
+            description("The requested size of the mapping is too large")
+        }
         NoLinkOrOsId {
             description("Tried to open mapping without flink path or os_id")
         }
@@ -35,12 +44,45 @@ quick_error! {
             display(x) -> ("{} : {}", x, err)
             cause(err)
         }
+        LinkCorrupt {
+            description("The link file is empty or does not contain a valid os_id")
+        }
         LinkDoesNotExist {
             description("Requested link file does not exist")
         }
+        LinkUnsupported {
+            description("The mapping cannot be opened through a link file")
+        }
+        LinkDirMissing(dir: std::path::PathBuf) {
+            description("The directory of the link file does not exist")
+            display(x) -> ("{} : {}", x, dir.display())
+        }
+        LinkRemoveFailed(err: std::io::Error) {
+            description("Removing the link file failed")
+            display(x) -> ("{} : {}", x, err)
+            cause(err)
+        }
         MappingIdExists {
             description("Shared memory OS specific ID already exists")
         }
+        MappingIdDoesNotExist {
+            description("Shared memory OS specific ID does not exist")
+        }
+        MemfdUnsupported {
+            description("The running kernel does not support memfd_create")
+        }
+        HugePagesUnavailable {
+            description("Huge pages could not be used for the mapping")
+        }
+        GlobalNamespaceDenied {
+            description("Creating a mapping in the Global namespace requires the SeCreateGlobalPrivilege")
+        }
+        OutOfMemory {
+            description("The system does not have enough memory for the mapping")
+        }
+        PermissionDenied {
+            description("The current process is not allowed to create the mapping")
+        }
         MapCreateFailed(err: u32) {
             description("Creating the shared memory failed")
             display(x) -> ("{} : os error {}", x, err)
@@ -49,6 +91,63 @@ quick_error! {
             description("Openning the shared memory failed")
             display(x) -> ("{} : os error {}", x, err)
         }
+        MapTooSmall(got: usize, required: usize) {
+            description("The opened mapping is smaller than the required size")
+            display(x) -> ("{} : got {} bytes, required {}", x, got, required)
+        }
+        BaseAddressUnavailable {
+            description("The mapping could not be placed at the desired base address")
+        }
+        RangeDoesNotFit {
+            description("The requested range does not fit inside the mapping")
+        }
+        NotOwner {
+            description("Only the owner of the mapping can perform this operation")
+        }
+        ResizeUnsupported {
+            description("Resizing this mapping is not supported")
+        }
+        ListUnsupported {
+            description("Listing existing mappings is not supported on this platform")
+        }
+        ListFailed(err: std::io::Error) {
+            description("Listing existing mappings failed")
+            display(x) -> ("{} : {}", x, err)
+            cause(err)
+        }
+        ResizeFailed(err: u32) {
+            description("Resizing the mapping failed")
+            display(x) -> ("{} : os error {}", x, err)
+        }
+        FlushFailed(err: u32) {
+            description("Flushing the mapping to its backing storage failed")
+            display(x) -> ("{} : os error {}", x, err)
+        }
+        ProtectFailed(err: u32) {
+            description("Changing the protection of the mapping failed")
+            display(x) -> ("{} : os error {}", x, err)
+        }
+        UnmapFailed(err: u32) {
+            description("Unmapping the mapping failed")
+            display(x) -> ("{} : os error {}", x, err)
+        }
+        UnlinkFailed(err: u32) {
+            description("Removing the OS specific ID of the mapping failed")
+            display(x) -> ("{} : os error {}", x, err)
+        }
+        CloseFailed(err: u32) {
+            description("Closing the handle to the mapping failed")
+            display(x) -> ("{} : os error {}", x, err)
+        }
+        Timeout {
+            description("Timed out waiting for the mapping to become available")
+        }
+        WriteOnReadOnly {
+            description("Tried to write to a mapping that was opened as read only")
+        }
+        AlignmentError {
+            description("The mapping is not properly aligned for the requested type")
+        }
         UnknownOsError(err: u32) {
             description("An unexpected OS error occured")
             display(x) -> ("{} : os error {}", x, err)
@@ -2,14 +2,44 @@
 //!
 //! For help on how to get started, take a look at the [examples](https://github.com/elast0ny/shared_memory-rs/tree/master/examples) !
 
-use std::fs::{File, OpenOptions};
+// ShmemError has more variants than quick_error! can expand with the default limit
+#![recursion_limit = "256"]
+
+use std::fs::{create_dir_all, File, OpenOptions};
 use std::io::{Read, Write};
 
-use std::fs::remove_file;
+use std::fs::{remove_file, rename};
+#[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
+#[cfg(target_os = "windows")]
+use std::os::windows::io::{AsRawHandle, IntoRawHandle, RawHandle};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use ::cfg_if::*;
 
+// Logging compiles to nothing unless the "logging" feature is enabled
+#[cfg(feature = "logging")]
+#[macro_use]
+extern crate log;
+#[cfg(not(feature = "logging"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+#[cfg(not(feature = "logging"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
 mod error;
 pub use error::*;
 
@@ -26,19 +56,114 @@ cfg_if! {
     }
 }
 
+/// Number of randomly generated os_ids tried by `create()` before giving up
+const MAX_ID_ATTEMPTS: usize = 10;
+/// Time `open_or_create()` waits for a racing creator to finish setting up the mapping
+const OPEN_OR_CREATE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Prefix of randomly generated os_ids
+const DEFAULT_OS_ID_PREFIX: &str = "/shmem_";
+/// Version of the JSON flink format
+#[cfg(feature = "json")]
+const FLINK_JSON_VERSION: u64 = 1;
+/// Maximum number of characters appended to the prefix of randomly generated os_ids
+const RANDOM_OS_ID_LEN: usize = 16;
+
+/// Memory protection applied to a range of a mapping with `Shmem::protect()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+    /// Any access faults
+    None,
+    /// Reads are allowed but writes fault
+    Read,
+    /// Reads and writes are allowed
+    ReadWrite,
+}
+
+/// Order in which `open()` uses the os_id and the flink to find the mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenStrategy {
+    /// Only use the os_id
+    OsIdOnly,
+    /// Only use the os_id stored in the flink
+    FlinkOnly,
+    /// Use the os_id, falling back to the flink if the mapping cannot be opened
+    OsIdThenFlink,
+    /// Use the flink, falling back to the os_id if the mapping cannot be opened
+    FlinkThenOsId,
+}
+
+/// Format of the flinks written by `create()`
+///
+/// Other formats are only available with their feature (e.g. `json`), so matches must have a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlinkFormat {
+    /// Only the os_id of the mapping (default)
+    Raw,
+    /// A JSON object holding the os_id, the size of the mapping and the version of the format
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Content of a flink
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlinkInfo {
+    /// The os_id of the mapping
+    pub os_id: String,
+    /// The size of the mapping requested by its creator, only stored in JSON flinks
+    pub size: Option<usize>,
+}
+
+/// Kernel object namespace holding the name of the mapping on windows
+#[cfg(target_os = "windows")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowsNamespace {
+    /// Only visible to processes in the same session (default)
+    Local,
+    /// Visible to processes in every session, creating a mapping requires the SeCreateGlobalPrivilege
+    Global,
+}
+
+/// Hook run on a freshly opened mapping, see `ShmemConf::on_open()`
+type OpenHook = Box<dyn FnOnce(&Shmem) -> Result<(), ShmemError> + Send>;
+
 /// Struct used to configure different parameters before creating a shared memory mapping
 pub struct ShmemConf {
     owner: bool,
     os_id: Option<String>,
+    os_id_prefix: String,
     overwrite_flink: bool,
+    create_flink_dirs: bool,
+    flink_format: FlinkFormat,
+    overwrite_mapping: bool,
     flink_path: Option<PathBuf>,
+    extra_flinks: Vec<PathBuf>,
     size: usize,
+    read_only: bool,
+    zero_on_create: bool,
+    huge_pages: bool,
+    huge_page_fallback: bool,
+    backing_file: Option<PathBuf>,
+    open_strategy: Option<OpenStrategy>,
+    desired_base: Option<usize>,
+    min_size: usize,
+    map_subset: Option<usize>,
+    name_generator: Option<Box<dyn FnMut() -> String + Send>>,
+    on_open: Option<OpenHook>,
+    #[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+    mode: Option<u32>,
+    #[cfg(target_os = "windows")]
+    security_descriptor: Option<String>,
+    #[cfg(target_os = "windows")]
+    namespace: Option<WindowsNamespace>,
+    #[cfg(target_os = "linux")]
+    use_memfd: bool,
 }
 impl Drop for ShmemConf {
     fn drop(&mut self) {
-        // Delete the flink if we are the owner of the mapping
+        // Delete the flinks if we are the owner of the mapping
         if self.owner {
-            if let Some(flink_path) = self.flink_path.as_ref() {
+            for flink_path in self.flink_path.iter().chain(&self.extra_flinks) {
                 let _ = remove_file(flink_path);
             }
         }
@@ -51,9 +176,33 @@ impl ShmemConf {
         Self {
             owner: false,
             os_id: None,
+            os_id_prefix: String::from(DEFAULT_OS_ID_PREFIX),
             overwrite_flink: false,
+            create_flink_dirs: false,
+            flink_format: FlinkFormat::Raw,
+            overwrite_mapping: false,
             flink_path: None,
+            extra_flinks: Vec::new(),
             size: 0,
+            read_only: false,
+            zero_on_create: true,
+            huge_pages: false,
+            huge_page_fallback: false,
+            backing_file: None,
+            open_strategy: None,
+            desired_base: None,
+            min_size: 0,
+            map_subset: None,
+            name_generator: None,
+            on_open: None,
+            #[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+            mode: None,
+            #[cfg(target_os = "windows")]
+            security_descriptor: None,
+            #[cfg(target_os = "windows")]
+            namespace: None,
+            #[cfg(target_os = "linux")]
+            use_memfd: false,
         }
     }
     /// Provide a specific os identifier for the mapping
@@ -64,12 +213,61 @@ impl ShmemConf {
         self
     }
 
+    /// Sets the prefix used for randomly generated os identifiers (defaults to "/shmem_")
+    ///
+    /// On unix, the prefix must start with a '/'
+    pub fn os_id_prefix<S: AsRef<str>>(mut self, prefix: S) -> Self {
+        self.os_id_prefix = String::from(prefix.as_ref());
+        self
+    }
+
+    /// Sets the function generating the part of the os_id that follows the prefix
+    ///
+    /// `create()` calls it again whenever the generated os_id is already in use.
+    /// By default, a random 64 bit number in hexadecimal is used.
+    pub fn name_generator<F: FnMut() -> String + Send + 'static>(mut self, generator: F) -> Self {
+        self.name_generator = Some(Box::new(generator));
+        self
+    }
+
+    /// Sets a hook that is run once on the opened mapping before `open()` returns it
+    ///
+    /// This is the place to validate an application header or to migrate an older layout.
+    /// When the hook returns an error, the mapping is unmapped and `open()` returns that error.
+    /// The hook is not run on mappings created by `open_or_create()`.
+    pub fn on_open<F>(mut self, hook: F) -> Self
+    where
+        F: FnOnce(&Shmem) -> Result<(), ShmemError> + Send + 'static,
+    {
+        self.on_open = Some(Box::new(hook));
+        self
+    }
+
     /// Overwrites file links if it already exist when calling `create()`
     pub fn force_create_flink(mut self) -> Self {
         self.overwrite_flink = true;
         self
     }
 
+    /// Creates the missing parent directories of the flinks when calling `create()`
+    ///
+    /// Otherwise, `create()` fails with `ShmemError::LinkDirMissing`
+    pub fn create_flink_dirs(mut self) -> Self {
+        self.create_flink_dirs = true;
+        self
+    }
+
+    /// Removes the existing mapping with the same os_id when calling `create()`
+    ///
+    /// This guarantees a fresh mapping, e.g. when a service restarts after a crash.
+    /// Processes that still have the old mapping open keep using it and never see the new one.
+    /// If the previous owner is still alive, dropping it unlinks the os_id of the new mapping.
+    /// On windows, a mapping cannot be removed while it is open so `create()` still fails.
+    pub fn force_create_mapping(mut self) -> Self {
+        self.overwrite_mapping = true;
+        self
+    }
+
     /// Create the shared memory mapping with a file link
     ///
     /// This creates a file on disk that contains the unique os_id for the mapping.
@@ -79,59 +277,371 @@ impl ShmemConf {
         self
     }
 
+    /// Adds another file link that `create()` writes the os_id to
+    ///
+    /// This lets clients find the mapping under different paths. `open()` only uses the path given to `flink()`.
+    pub fn add_flink<S: AsRef<Path>>(mut self, path: S) -> Self {
+        self.extra_flinks.push(PathBuf::from(path.as_ref()));
+        self
+    }
+
+    /// Sets the format of the flinks written by `create()`
+    ///
+    /// `open()` reads flinks in any format, as long as the `json` feature is enabled for JSON flinks
+    pub fn flink_format(mut self, format: FlinkFormat) -> Self {
+        self.flink_format = format;
+        self
+    }
+
     /// Sets the size of the mapping that will be used in `create()`
     pub fn size(mut self, size: usize) -> Self {
         self.size = size;
         self
     }
 
-    /// Create a new mapping using the current configuration
-    pub fn create(mut self) -> Result<Shmem, ShmemError> {
+    /// Maps the memory without write permissions when calling `open()`
+    ///
+    /// This allows opening mappings that the current process only has read access to.
+    /// No write access is required anywhere : the flink is only ever read by `open()`.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Fills the mapping with zeroes when calling `create()` (default)
+    ///
+    /// Writing to every page also commits them, so the whole mapping is backed by memory right away
+    pub fn zero_on_create(mut self) -> Self {
+        self.zero_on_create = true;
+        self
+    }
+
+    /// Leaves the initial content of the mapping up to the OS when calling `create()`
+    ///
+    /// This avoids touching every page of large mappings
+    pub fn no_zero_on_create(mut self) -> Self {
+        self.zero_on_create = false;
+        self
+    }
+
+    /// Backs the mapping with an anonymous memfd instead of a named shm object when calling `create()`
+    ///
+    /// The mapping does not appear in /dev/shm and cannot be opened by its os_id (which is only a label).
+    /// Other processes must instead receive the file descriptor (see `as_raw_fd()`) and use `open_fd()`.
+    /// For the same reason, `create()` fails with `ShmemError::LinkUnsupported` when a flink is set.
+    #[cfg(target_os = "linux")]
+    pub fn use_memfd(mut self) -> Self {
+        self.use_memfd = true;
+        self
+    }
+
+    /// Backs the mapping with a file on disk instead of an OS shared memory object
+    ///
+    /// `create()` creates the file (failing if it already exists) and `open()` maps an existing one.
+    /// The content of the mapping persists in the file after every process is done with it.
+    /// When set, the os_id of the mapping is the path of the file. The file identifies the mapping on
+    /// its own, so `create()` fails with `ShmemError::LinkUnsupported` when a flink is set as well.
+    pub fn backing_file<S: AsRef<Path>>(mut self, path: S) -> Self {
+        self.backing_file = Some(PathBuf::from(path.as_ref()));
+        self
+    }
+
+    /// Sets the permission bits of the shm object created by `create()` (e.g. 0o640)
+    ///
+    /// When not specified, only the current user can open the mapping. This only applies to named shm objects :
+    /// a `backing_file()` is created with the default permissions of files and a memfd cannot be opened by name.
+    #[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets the security descriptor (in SDDL format) of the section created by `create()`
+    ///
+    /// When not specified, the default security descriptor of the process is used
+    #[cfg(target_os = "windows")]
+    pub fn security_descriptor<S: AsRef<str>>(mut self, sddl: S) -> Self {
+        self.security_descriptor = Some(String::from(sddl.as_ref()));
+        self
+    }
+
+    /// Sets the namespace of the os_id used by `create()` and `open()`
+    ///
+    /// `Global` lets services share mappings with processes of other sessions.
+    /// The namespace is prepended to the os_id (or to the prefix of generated ones) and is part of `get_os_id()`.
+    #[cfg(target_os = "windows")]
+    pub fn windows_namespace(mut self, namespace: WindowsNamespace) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Backs the mapping with huge pages when calling `create()`
+    ///
+    /// The size of the mapping is rounded up to a multiple of the huge page size.
+    /// On linux, this requires `use_memfd()` and huge pages to be reserved by the system (see vm.nr_hugepages).
+    /// On windows, the process needs the SeLockMemoryPrivilege ("Lock pages in memory") to be enabled.
+    ///
+    /// When huge pages are unavailable, `create()` fails with `ShmemError::HugePagesUnavailable`
+    pub fn use_huge_pages(mut self) -> Self {
+        self.huge_pages = true;
+        self
+    }
+
+    /// Falls back to regular pages when huge pages requested by `use_huge_pages()` are unavailable
+    pub fn allow_huge_page_fallback(mut self) -> Self {
+        self.huge_page_fallback = true;
+        self
+    }
+
+    /// Sets how `open()` finds the mapping when both an os_id and a flink are available
+    ///
+    /// By default, the os_id is read from the flink (if any) and must match the provided os_id (if any)
+    pub fn open_strategy(mut self, strategy: OpenStrategy) -> Self {
+        self.open_strategy = Some(strategy);
+        self
+    }
+
+    /// Maps the memory at a specific address when calling `create()` or `open()`
+    ///
+    /// This allows storing absolute pointers inside the mapping as long as every process uses the same base.
+    /// The address must be page aligned and is only a hint to the OS : if anything is already mapped there
+    /// (shared libraries, heap, other mappings...), `create()`/`open()` fail with `ShmemError::BaseAddressUnavailable`
+    /// instead of silently mapping the memory elsewhere.
+    ///
+    /// The layout of the address space differs between processes, OSes and runs (ASLR), so there is never
+    /// a guarantee that a given address will be available. Pick an address far from the usual heap and
+    /// library ranges and be ready to handle failures.
+    pub fn desired_base(mut self, addr: usize) -> Self {
+        self.desired_base = Some(addr);
+        self
+    }
+
+    /// Makes `open()` fail with `ShmemError::MapTooSmall` when the mapping is smaller than `size` bytes
+    ///
+    /// This catches creators that were built with a smaller layout than the current process expects.
+    /// The whole mapping is checked, even when only its beginning is mapped with `map_subset()`.
+    /// On windows, the size is only known to a page unless the mapping is opened through a JSON flink.
+    pub fn require_min_size(mut self, size: usize) -> Self {
+        self.min_size = size;
+        self
+    }
+
+    /// Only maps the first `len` bytes of the mapping when calling `open()`
+    ///
+    /// This saves address space when only the beginning of a large mapping is needed, `len()` then returns `len`.
+    /// `open()` fails with `ShmemError::MapTooSmall` when the mapping is smaller than `len`.
+    pub fn map_subset(mut self, len: usize) -> Self {
+        self.map_subset = Some(len);
+        self
+    }
+
+    /// Returns whether a mapping with this os_id currently exists
+    ///
+    /// This never creates nor maps the object. Mappings that exist but that the current process
+    /// is not allowed to open are reported as existing.
+    pub fn os_id_exists<S: AsRef<str>>(os_id: S) -> bool {
+        os_impl::mapping_exists(os_id.as_ref())
+    }
+
+    /// Returns whether a flink currently exists at this path
+    pub fn flink_exists<S: AsRef<Path>>(path: S) -> bool {
+        path.as_ref().exists()
+    }
+
+    /// Checks the configuration for `create()` without touching the OS
+    ///
+    /// This returns the first error that `create()` would report before creating anything
+    pub fn validate(&self) -> Result<(), ShmemError> {
         if self.size == 0 {
             return Err(ShmemError::MapSizeZero);
         }
+        // Slices over the mapping cannot exceed isize::MAX bytes
+        if self.size > isize::MAX as usize {
+            return Err(ShmemError::MapSizeTooLarge);
+        }
+        // The creator must be able to initialize the mapping
+        if self.read_only {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+
+        // The os_id of a memfd is only a label and the one of a backing file is a path,
+        // other processes could not open them through a flink
+        let has_flinks = self.flink_path.is_some() || !self.extra_flinks.is_empty();
+        if has_flinks && self.backing_file.is_some() {
+            return Err(ShmemError::LinkUnsupported);
+        }
+        #[cfg(target_os = "linux")]
+        if has_flinks && self.use_memfd {
+            return Err(ShmemError::LinkUnsupported);
+        }
+
+        // Validate the requested os_id or the prefix used to generate one
+        match self.os_id {
+            Some(ref specific_id) if specific_id.len() > os_impl::MAX_OS_ID_LEN => {
+                return Err(ShmemError::OsIdTooLong);
+            }
+            // Generated names are checked by create() once they are known
+            None if self.name_generator.is_none()
+                && self.os_id_prefix.len() + RANDOM_OS_ID_LEN > os_impl::MAX_OS_ID_LEN =>
+            {
+                return Err(ShmemError::OsIdTooLong);
+            }
+            #[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+            None if !self.os_id_prefix.starts_with('/') => {
+                return Err(ShmemError::InvalidOsIdPrefix);
+            }
+            _ => {}
+        };
+
+        Ok(())
+    }
+
+    /// Create a new mapping using the current configuration
+    pub fn create(mut self) -> Result<Shmem, ShmemError> {
+        let mapping = self.create_inner()?;
+
+        Ok(Shmem {
+            config: self,
+            mapping,
+        })
+    }
+
+    /// Creates the mapping and its flinks, leaving the configuration ready to be wrapped in a `Shmem`
+    fn create_inner(&mut self) -> Result<os_impl::MapData, ShmemError> {
+        #[cfg(target_os = "windows")]
+        self.apply_namespace();
+        self.validate()?;
 
         // Create the mapping
-        let mapping = match self.os_id {
+        let mut mapping = match self.os_id {
+            // The backing file identifies the mapping
+            _ if self.backing_file.is_some() => self.create_os_mapping("")?,
             None => {
-                // Generate random ID until one works
-                loop {
-                    let cur_id = format!("/shmem_{:X}", rand::random::<u64>());
-                    match os_impl::create_mapping(&cur_id, self.size) {
-                        Err(ShmemError::MappingIdExists) => continue,
-                        Ok(m) => break m,
-                        Err(e) => return Err(e),
+                // Generate IDs until one works
+                let mut generator = self.name_generator.take();
+                let mut attempt = 0;
+                let res = loop {
+                    let cur_id = match generator {
+                        Some(ref mut f) => format!("{}{}", self.os_id_prefix, f()),
+                        None => format!("{}{:X}", self.os_id_prefix, rand::random::<u64>()),
+                    };
+                    if cur_id.len() > os_impl::MAX_OS_ID_LEN {
+                        break Err(ShmemError::OsIdTooLong);
+                    }
+                    match self.create_os_mapping(&cur_id) {
+                        Err(ShmemError::MappingIdExists) if attempt + 1 < MAX_ID_ATTEMPTS => {
+                            trace!("os_id {} already exists, trying another one", cur_id);
+                            attempt += 1;
+                            continue;
+                        }
+                        res => break res,
                     };
+                };
+                // open_or_create() may call us again
+                self.name_generator = generator;
+                res?
+            }
+            Some(ref specific_id) => {
+                if self.overwrite_mapping {
+                    let _ = os_impl::remove_mapping(specific_id);
                 }
+                self.create_os_mapping(specific_id)?
             }
-            Some(ref specific_id) => os_impl::create_mapping(specific_id, self.size)?,
         };
 
-        // Create flink
-        if let Some(ref flink_path) = self.flink_path {
-            let mut open_options: OpenOptions = OpenOptions::new();
-            open_options.write(true);
-            if self.overwrite_flink {
-                open_options.truncate(true);
-            } else {
-                open_options.create_new(true);
+        // A backing file left behind would make every later create() fail
+        if let Err(e) = self.init_created(&mut mapping) {
+            if let Some(ref path) = self.backing_file {
+                drop(mapping);
+                let _ = remove_file(path);
             }
+            return Err(e);
+        }
 
-            match open_options.open(flink_path) {
-                Ok(mut f) => {
-                    // Write the os_id in the flink
-                    if let Err(e) = f.write(mapping.unique_id.as_bytes()) {
-                        return Err(ShmemError::LinkWriteFailed(e));
-                    }
-                }
-                Err(e) => {
-                    return Err(match e.kind() {
-                        std::io::ErrorKind::AlreadyExists => ShmemError::LinkExists,
-                        _ => ShmemError::LinkCreateFailed(e),
-                    });
+        self.owner = true;
+        self.os_id = Some(mapping.unique_id.clone());
+        debug!(
+            "Created mapping {} of {} bytes",
+            mapping.unique_id, mapping.map_size
+        );
+
+        Ok(mapping)
+    }
+
+    /// Places, initializes and links a freshly created mapping
+    fn init_created(&mut self, mapping: &mut os_impl::MapData) -> Result<(), ShmemError> {
+        if let Some(base) = self.desired_base {
+            mapping.remap_at(base, false)?;
+        }
+
+        if self.zero_on_create {
+            unsafe { std::ptr::write_bytes(mapping.map_ptr, 0, mapping.map_size) };
+        }
+
+        // Huge pages round the size of the object up
+        self.size = mapping.map_size;
+
+        // Create the flinks, removing the ones already written if any of them fails
+        let flink_contents = self.flink_contents(&mapping.unique_id);
+        let flink_paths: Vec<&PathBuf> = self.flink_path.iter().chain(&self.extra_flinks).collect();
+        for (i, flink_path) in flink_paths.iter().enumerate() {
+            if let Err(e) = write_flink(
+                flink_path,
+                &flink_contents,
+                self.overwrite_flink,
+                self.create_flink_dirs,
+            ) {
+                for written in &flink_paths[..i] {
+                    let _ = remove_file(written);
                 }
-            };
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns what the flinks of the mapping `os_id` contain in the configured format
+    fn flink_contents(&self, os_id: &str) -> String {
+        match self.flink_format {
+            FlinkFormat::Raw => String::from(os_id),
+            #[cfg(feature = "json")]
+            FlinkFormat::Json => serde_json::json!({
+                "os_id": os_id,
+                "size": self.size,
+                "version": FLINK_JSON_VERSION,
+            })
+            .to_string(),
         }
+    }
+
+    /// Create a new anonymous mapping that can only be shared with child processes
+    ///
+    /// The mapping has no os_id nor flink, so it cannot be `open()`ed by other processes.
+    /// On unix, children created with `fork()` after this call inherit the mapping at the same address
+    /// and there is no file descriptor to pass around (`as_raw_fd()` returns -1).
+    /// On windows, the section handle is inheritable and must be passed to the child process.
+    ///
+    /// Setting a flink returns `ShmemError::LinkUnsupported`. The options describing how to back or place
+    /// a named mapping (`backing_file()`, `desired_base()`, `use_huge_pages()`, `use_memfd()`, `mode()`,
+    /// `security_descriptor()`) are ignored. Anonymous mappings cannot `grow()`.
+    pub fn create_anonymous(mut self) -> Result<Shmem, ShmemError> {
+        if self.size == 0 {
+            return Err(ShmemError::MapSizeZero);
+        }
+        if self.size > isize::MAX as usize {
+            return Err(ShmemError::MapSizeTooLarge);
+        }
+        // The creator must be able to initialize the mapping
+        if self.read_only {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        // Nothing could open the mapping through a flink
+        if self.flink_path.is_some() || !self.extra_flinks.is_empty() {
+            return Err(ShmemError::LinkUnsupported);
+        }
+
+        let mapping = os_impl::create_anonymous_mapping(self.size)?;
 
         self.owner = true;
         self.size = mapping.map_size;
@@ -142,66 +652,517 @@ impl ShmemConf {
         })
     }
 
+    /// Creates the OS mapping with the configured backing
+    fn create_os_mapping(&self, unique_id: &str) -> Result<os_impl::MapData, ShmemError> {
+        match self.create_os_mapping_with(unique_id, self.huge_pages) {
+            Err(ShmemError::HugePagesUnavailable) if self.huge_page_fallback => {
+                self.create_os_mapping_with(unique_id, false)
+            }
+            res => res,
+        }
+    }
+    fn create_os_mapping_with(
+        &self,
+        unique_id: &str,
+        huge_pages: bool,
+    ) -> Result<os_impl::MapData, ShmemError> {
+        if let Some(ref path) = self.backing_file {
+            if huge_pages {
+                return Err(ShmemError::HugePagesUnavailable);
+            }
+            return os_impl::create_file_mapping(path, self.size);
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if self.use_memfd {
+                return os_impl::create_memfd_mapping(unique_id, self.size, huge_pages);
+            }
+        }
+        #[cfg(target_os = "windows")]
+        let permissions = self.security_descriptor.as_deref();
+        #[cfg(not(target_os = "windows"))]
+        let permissions = self.mode;
+        os_impl::create_mapping(unique_id, self.size, huge_pages, permissions)
+    }
+
+    /// Opens an existing mapping from a file descriptor (e.g. a memfd received over a unix socket)
+    ///
+    /// Ownership of the file descriptor is transferred to the returned `Shmem`
+    #[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+    pub fn open_fd(mut self, fd: RawFd) -> Result<Shmem, ShmemError> {
+        let mut mapping = os_impl::from_raw_fd(fd, self.read_only, self.map_subset)?;
+        self.size = self.check_opened(&mut mapping, None)?;
+        self.owner = false;
+
+        self.into_opened(mapping)
+    }
+
+    /// Opens an existing mapping from a section handle (e.g. duplicated from another process)
+    ///
+    /// Ownership of the handle is transferred to the returned `Shmem`
+    #[cfg(target_os = "windows")]
+    pub fn open_handle(mut self, handle: RawHandle) -> Result<Shmem, ShmemError> {
+        let mut mapping = os_impl::from_raw_handle(handle as _, self.read_only, self.map_subset)?;
+        self.size = self.check_opened(&mut mapping, None)?;
+        self.owner = false;
+
+        self.into_opened(mapping)
+    }
+
     /// Opens an existing mapping using the current configuration
     pub fn open(mut self) -> Result<Shmem, ShmemError> {
-        // Must at least have a flink or an os_id
-        if self.flink_path.is_none() && self.os_id.is_none() {
-            return Err(ShmemError::NoLinkOrOsId);
-        }
-
-        // Get the os_id from the flink
-        if let Some(ref flink_path) = self.flink_path {
-            let mut f = match File::open(flink_path) {
-                Ok(f) => f,
-                Err(e) => return Err(ShmemError::LinkOpenFailed(e)),
-            };
-            let mut contents: Vec<u8> = Vec::new();
-            if let Err(e) = f.read_to_end(&mut contents) {
-                return Err(ShmemError::LinkReadFailed(e));
-            }
-            let link_os_id = match String::from_utf8(contents) {
-                Ok(s) => s,
-                Err(_) => return Err(ShmemError::LinkDoesNotExist),
-            };
-            if let Some(os_id) = self.os_id.as_ref() {
-                if *os_id != link_os_id {
-                    return Err(ShmemError::FlinkInvalidOsId);
+        let mapping = self.open_inner()?;
+        self.into_opened(mapping)
+    }
+
+    /// Creates the mapping or opens it if it already exists
+    ///
+    /// The returned boolean is true when this call created the mapping, in which case the caller
+    /// is responsible for initializing it. When several processes race, exactly one of them creates the mapping.
+    /// The mapping must be identified by a flink, an os_id or a backing file.
+    /// With `read_only()`, the mapping is never created and this waits for another process to create it.
+    /// `force_create_flink()` and `force_create_mapping()` are ignored as they would let every process create it.
+    pub fn open_or_create(mut self) -> Result<(Shmem, bool), ShmemError> {
+        self.overwrite_flink = false;
+        self.overwrite_mapping = false;
+
+        let start = Instant::now();
+        let mut delay = Duration::from_millis(1);
+        loop {
+            // Read only processes cannot initialize the mapping
+            if !self.read_only {
+                match self.create_inner() {
+                    Ok(mapping) => {
+                        return Ok((
+                            Shmem {
+                                config: self,
+                                mapping,
+                            },
+                            true,
+                        ))
+                    }
+                    Err(ShmemError::LinkExists) | Err(ShmemError::MappingIdExists) => {}
+                    Err(e) => return Err(e),
                 }
-            } else {
-                self.os_id = Some(link_os_id);
             }
+
+            match self.open_inner() {
+                Ok(mapping) => return Ok((self.into_opened(mapping)?, false)),
+                // The creator is still setting up the mapping or gave up in the meantime
+                Err(ref e) if self.is_not_ready(e) && start.elapsed() < OPEN_OR_CREATE_TIMEOUT => {}
+                Err(e) => return Err(e),
+            }
+
+            std::thread::sleep(delay);
+            delay = (delay * 2).min(Duration::from_millis(100));
+        }
+    }
+
+    /// Opens the mapping, waiting for it to be created by another process
+    ///
+    /// `open()` is retried with an increasing delay as long as the mapping, its flink or its backing file
+    /// do not exist yet, or while the flink is still empty. Any other error (e.g. a corrupt flink) is returned
+    /// right away. When `timeout` elapses, `ShmemError::Timeout` is returned.
+    pub fn open_blocking(mut self, timeout: Option<Duration>) -> Result<Shmem, ShmemError> {
+        let start = Instant::now();
+        let mut delay = Duration::from_millis(1);
+        loop {
+            match self.open_inner() {
+                Ok(mapping) => return self.into_opened(mapping),
+                Err(ref e) if self.is_not_ready(e) => {}
+                Err(e) => return Err(e),
+            }
+
+            if let Some(timeout) = timeout {
+                let elapsed = start.elapsed();
+                if elapsed >= timeout {
+                    return Err(ShmemError::Timeout);
+                }
+                delay = delay.min(timeout - elapsed);
+            }
+            std::thread::sleep(delay);
+            delay = (delay * 2).min(Duration::from_millis(100));
         }
+    }
 
-        let os_id = match self.os_id.as_ref() {
-            Some(i) => i,
-            None => return Err(ShmemError::NoLinkOrOsId),
+    /// Wraps an opened mapping in a `Shmem`, running the `on_open` hook on it
+    fn into_opened(mut self, mapping: os_impl::MapData) -> Result<Shmem, ShmemError> {
+        let hook = self.on_open.take();
+        let shmem = Shmem {
+            config: self,
+            mapping,
         };
 
-        let mapping = os_impl::open_mapping(os_id)?;
+        // Dropping shmem on error unmaps it
+        if let Some(hook) = hook {
+            hook(&shmem)?;
+        }
+        Ok(shmem)
+    }
 
-        self.size = mapping.map_size;
+    /// Opens the mapping, leaving the configuration ready to be wrapped in a `Shmem`
+    fn open_inner(&mut self) -> Result<os_impl::MapData, ShmemError> {
+        #[cfg(target_os = "windows")]
+        self.apply_namespace();
+
+        // The backing file identifies the mapping
+        if let Some(ref path) = self.backing_file {
+            let mut mapping = os_impl::open_file_mapping(path, self.read_only, self.map_subset)?;
+            self.size = self.check_opened(&mut mapping, None)?;
+            self.owner = false;
+
+            return Ok(mapping);
+        }
+
+        // JSON flinks also provide the size of the mapping
+        let (mut mapping, flink_size) = match self.open_strategy {
+            None => {
+                // Must at least have a flink or an os_id
+                if self.flink_path.is_none() && self.os_id.is_none() {
+                    return Err(ShmemError::NoLinkOrOsId);
+                }
+
+                // Get the os_id from the flink
+                if let Some(ref flink_path) = self.flink_path {
+                    let info = read_flink_info(flink_path)?;
+                    if let Some(os_id) = self.os_id.as_ref() {
+                        if *os_id != info.os_id {
+                            return Err(ShmemError::FlinkInvalidOsId);
+                        }
+                    }
+                    let mapping =
+                        os_impl::open_mapping(&info.os_id, self.read_only, self.map_subset)?;
+                    (mapping, info.size)
+                } else {
+                    (self.open_os_id()?, None)
+                }
+            }
+            Some(OpenStrategy::OsIdOnly) => (self.open_os_id()?, None),
+            Some(OpenStrategy::FlinkOnly) => self.open_flink()?,
+            Some(OpenStrategy::OsIdThenFlink) => match self.open_os_id() {
+                Ok(m) => (m, None),
+                Err(_) => self.open_flink()?,
+            },
+            Some(OpenStrategy::FlinkThenOsId) => match self.open_flink() {
+                Ok(v) => v,
+                Err(_) => (self.open_os_id()?, None),
+            },
+        };
+        self.size = self.check_opened(&mut mapping, flink_size)?;
+
+        self.os_id = Some(mapping.unique_id.clone());
         self.owner = false;
+        debug!(
+            "Opened mapping {} of {} bytes",
+            mapping.unique_id, mapping.map_size
+        );
 
-        Ok(Shmem {
-            config: self,
-            mapping,
-        })
+        Ok(mapping)
+    }
+
+    /// Prepends the configured namespace to the os_id and to the prefix of generated os_ids
+    #[cfg(target_os = "windows")]
+    fn apply_namespace(&mut self) {
+        let namespace = match self.namespace {
+            Some(WindowsNamespace::Local) => "Local\\",
+            Some(WindowsNamespace::Global) => "Global\\",
+            None => return,
+        };
+        let add_namespace = |id: &str| {
+            if id.starts_with(namespace) {
+                String::from(id)
+            } else {
+                format!("{}{}", namespace, id)
+            }
+        };
+        self.os_id = self.os_id.as_deref().map(add_namespace);
+        self.os_id_prefix = add_namespace(&self.os_id_prefix);
+    }
+
+    /// Applies the configured requirements to a freshly opened mapping and returns its size
+    ///
+    /// `flink_size` is the exact size stored in a JSON flink, the OS may only know it to a page
+    fn check_opened(
+        &self,
+        mapping: &mut os_impl::MapData,
+        flink_size: Option<usize>,
+    ) -> Result<usize, ShmemError> {
+        let object_size = match flink_size {
+            Some(size) if size <= mapping.object_size => size,
+            _ => mapping.object_size,
+        };
+        // The whole mapping must be large enough, even if only its beginning is mapped
+        if object_size < self.min_size {
+            return Err(ShmemError::MapTooSmall(object_size, self.min_size));
+        }
+        if let Some(base) = self.desired_base {
+            mapping.remap_at(base, self.read_only)?;
+        }
+        Ok(mapping.map_size.min(object_size))
+    }
+
+    /// Returns whether `open()` failed because the mapping is not completely created yet
+    fn is_not_ready(&self, e: &ShmemError) -> bool {
+        match e {
+            ShmemError::LinkOpenFailed(e) => e.kind() == std::io::ErrorKind::NotFound,
+            // The creator has not written the flink yet, any other corrupt flink stays corrupt
+            ShmemError::LinkCorrupt => match self.flink_path {
+                Some(ref flink_path) => matches!(flink_path.metadata(), Ok(m) if m.len() == 0),
+                None => false,
+            },
+            ShmemError::MappingIdDoesNotExist | ShmemError::MapSizeZero => true,
+            _ => false,
+        }
+    }
+
+    /// Opens the mapping using the configured os_id
+    fn open_os_id(&self) -> Result<os_impl::MapData, ShmemError> {
+        match self.os_id {
+            Some(ref os_id) => os_impl::open_mapping(os_id, self.read_only, self.map_subset),
+            None => Err(ShmemError::NoLinkOrOsId),
+        }
+    }
+
+    /// Opens the mapping using the os_id found in the configured flink, along with the size it stores
+    fn open_flink(&self) -> Result<(os_impl::MapData, Option<usize>), ShmemError> {
+        match self.flink_path {
+            Some(ref flink_path) => {
+                let info = read_flink_info(flink_path)?;
+                let mapping = os_impl::open_mapping(&info.os_id, self.read_only, self.map_subset)?;
+                Ok((mapping, info.size))
+            }
+            None => Err(ShmemError::NoLinkOrOsId),
+        }
+    }
+}
+
+/// Writes the content of a new flink
+fn write_flink(
+    flink_path: &Path,
+    contents: &str,
+    overwrite: bool,
+    create_dirs: bool,
+) -> Result<(), ShmemError> {
+    // Report a missing directory instead of an opaque io error
+    if let Some(dir) = flink_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if create_dirs {
+            if let Err(e) = create_dir_all(dir) {
+                return Err(ShmemError::LinkCreateFailed(e));
+            }
+        } else if !dir.is_dir() {
+            return Err(ShmemError::LinkDirMissing(dir.to_path_buf()));
+        }
+    }
+
+    let mut open_options: OpenOptions = OpenOptions::new();
+    open_options.write(true);
+    if overwrite {
+        open_options.truncate(true);
+    } else {
+        open_options.create_new(true);
+    }
+
+    match open_options.open(flink_path) {
+        Ok(mut f) => {
+            // Write the whole content in the flink and make sure it hits the disk
+            // before anyone can open() it
+            if let Err(e) = f.write_all(contents.as_bytes()).and_then(|_| f.sync_all()) {
+                return Err(ShmemError::LinkWriteFailed(e));
+            }
+        }
+        Err(e) => {
+            return Err(match e.kind() {
+                std::io::ErrorKind::AlreadyExists => ShmemError::LinkExists,
+                _ => ShmemError::LinkCreateFailed(e),
+            });
+        }
+    };
+
+    Ok(())
+}
+
+/// Atomically replaces the content of a flink by writing a temporary file next to it and renaming it
+fn replace_flink(flink_path: &Path, contents: &str) -> Result<(), ShmemError> {
+    // Concurrent relinks each need their own temporary file
+    static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut tmp_name = flink_path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = flink_path.with_file_name(tmp_name);
+
+    let mut f = match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)
+    {
+        Ok(f) => f,
+        Err(e) => return Err(ShmemError::LinkCreateFailed(e)),
+    };
+    let res = f
+        .write_all(contents.as_bytes())
+        .and_then(|_| f.sync_all())
+        .and_then(|_| rename(&tmp_path, flink_path));
+    if let Err(e) = res {
+        let _ = remove_file(&tmp_path);
+        return Err(ShmemError::LinkWriteFailed(e));
+    }
+
+    Ok(())
+}
+
+/// Reads the content of a flink in any of the `FlinkFormat`s
+///
+/// JSON flinks can only be read when the `json` feature is enabled
+pub fn read_flink_info<S: AsRef<Path>>(flink_path: S) -> Result<FlinkInfo, ShmemError> {
+    let mut f = match File::open(flink_path.as_ref()) {
+        Ok(f) => f,
+        Err(e) => return Err(ShmemError::LinkOpenFailed(e)),
+    };
+    let mut contents: Vec<u8> = Vec::new();
+    if let Err(e) = f.read_to_end(&mut contents) {
+        return Err(ShmemError::LinkReadFailed(e));
+    }
+    // Ignore trailing newlines that editors may append
+    let contents = match String::from_utf8(contents) {
+        Ok(s) if !s.trim_end().is_empty() => String::from(s.trim_end()),
+        _ => return Err(ShmemError::LinkCorrupt),
+    };
+
+    if contents.starts_with('{') {
+        return parse_json_flink(&contents);
+    }
+    Ok(FlinkInfo {
+        os_id: contents,
+        size: None,
+    })
+}
+
+/// Parses the content of a JSON flink
+#[cfg(feature = "json")]
+fn parse_json_flink(contents: &str) -> Result<FlinkInfo, ShmemError> {
+    let value: serde_json::Value = match serde_json::from_str(contents) {
+        Ok(v) => v,
+        Err(_) => return Err(ShmemError::LinkCorrupt),
+    };
+    if value["version"].as_u64() != Some(FLINK_JSON_VERSION) {
+        return Err(ShmemError::LinkCorrupt);
+    }
+    match value["os_id"].as_str() {
+        Some(os_id) if !os_id.is_empty() => Ok(FlinkInfo {
+            os_id: String::from(os_id),
+            size: value["size"].as_u64().map(|v| v as usize),
+        }),
+        _ => Err(ShmemError::LinkCorrupt),
+    }
+}
+
+/// JSON flinks cannot be read without the `json` feature
+#[cfg(not(feature = "json"))]
+fn parse_json_flink(_contents: &str) -> Result<FlinkInfo, ShmemError> {
+    Err(ShmemError::LinkCorrupt)
+}
+
+/// Returns the os_ids of the existing mappings whose os_id starts with `prefix` (e.g. "/shmem_")
+///
+/// The mappings are neither opened nor modified. This is only supported on linux, where
+/// named shm objects are listed from the files in /dev/shm. Other platforms return `ShmemError::ListUnsupported`.
+pub fn list_mappings<S: AsRef<str>>(prefix: S) -> Result<Vec<String>, ShmemError> {
+    os_impl::list_mappings(prefix.as_ref())
+}
+
+/// Removes the mapping identified by `os_id` without opening it
+///
+/// This is meant to clean up mappings left behind by crashed owners. Processes that have the mapping
+/// open keep using it, but it cannot be opened anymore. On windows, named mappings are destroyed
+/// as soon as no process has them open, so this only checks that the mapping exists.
+pub fn force_remove<S: AsRef<str>>(os_id: S) -> Result<(), ShmemError> {
+    os_impl::remove_mapping(os_id.as_ref())
+}
+
+/// Removes a flink left behind by a crashed owner
+pub fn force_remove_flink<S: AsRef<Path>>(path: S) -> Result<(), ShmemError> {
+    match remove_file(path.as_ref()) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(ShmemError::LinkDoesNotExist),
+        Err(e) => Err(ShmemError::LinkRemoveFailed(e)),
+    }
+}
+
+/// Returns the page size of the OS, which mapped sizes are rounded up to
+///
+/// The value is queried once and cached. Mappings always start on a page boundary.
+pub fn page_size() -> usize {
+    static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+    match PAGE_SIZE.load(Ordering::Relaxed) {
+        0 => {
+            let page_size = os_impl::page_size();
+            PAGE_SIZE.store(page_size, Ordering::Relaxed);
+            page_size
+        }
+        page_size => page_size,
     }
 }
 
 /// Structure used to extract information from an existing shared memory mapping
+///
+/// Dropping an owned `Shmem` cleans up the mapping's OS identifier and flink (see `set_owner()`)
 pub struct Shmem {
     config: ShmemConf,
     mapping: os_impl::MapData,
 }
+// The mapping is valid from any thread of the process and Shmem holds no thread-bound state.
+// Sync is deliberately not implemented : concurrent accesses to the memory must be synchronized by the user.
+unsafe impl Send for Shmem {}
+
+impl std::fmt::Debug for Shmem {
+    /// Summarizes the mapping without its content
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Shmem")
+            .field("os_id", &self.get_os_id())
+            .field("flink", &self.get_flink_path())
+            .field("len", &self.len())
+            .field("mapped_size", &self.mapped_size())
+            .field("owner", &self.is_owner())
+            .field("read_only", &self.is_read_only())
+            .finish()
+    }
+}
+
 #[allow(clippy::len_without_is_empty)]
 impl Shmem {
+    /// Creates a mapping of `size` bytes identified by the flink at `flink_path`
+    ///
+    /// Shortcut for `ShmemConf::new().size(size).flink(flink_path).create()`
+    ///
+    /// ```
+    /// use shared_memory::Shmem;
+    ///
+    /// # let flink = std::env::temp_dir().join(format!("shmem_doc_{}", std::process::id()));
+    /// let creator = Shmem::create(&flink, 4096).unwrap();
+    /// let opener = Shmem::open(&flink).unwrap();
+    /// # assert_eq!(opener.get_os_id(), creator.get_os_id());
+    /// ```
+    pub fn create<S: AsRef<Path>>(flink_path: S, size: usize) -> Result<Shmem, ShmemError> {
+        ShmemConf::new().size(size).flink(flink_path).create()
+    }
+    /// Opens the mapping identified by the flink at `flink_path`
+    ///
+    /// Shortcut for `ShmemConf::new().flink(flink_path).open()`
+    pub fn open<S: AsRef<Path>>(flink_path: S) -> Result<Shmem, ShmemError> {
+        ShmemConf::new().flink(flink_path).open()
+    }
     /// Returns whether we created the mapping or not
     pub fn is_owner(&self) -> bool {
         self.config.owner
     }
     /// Allows for gaining/releasing ownership of the mapping
     ///
+    /// When dropped, the owner removes the flink and (on unix) unlinks the os_id of the mapping.
+    /// Releasing ownership lets the mapping outlive this `Shmem`, e.g. when a long-lived name must survive its creator.
+    ///
     /// Warning : You must ensure at least one process owns the mapping in order to ensure proper cleanup code is ran
     pub fn set_owner(&mut self, is_owner: bool) -> bool {
         
@@ -212,7 +1173,44 @@ impl Shmem {
         self.config.owner = is_owner;
         prev_val
     }
+    /// Unmaps the memory from the current process without removing the mapping nor its flink
+    ///
+    /// Unlike dropping an owning `Shmem`, this never unlinks the os_id, so other processes can
+    /// keep opening the mapping. Ownership is effectively handed off and someone else must clean up.
+    /// On windows, the mapping is destroyed as soon as no process has it open anymore.
+    pub fn detach(mut self) {
+        self.set_owner(false);
+    }
+    /// Unmaps the memory and cleans up like dropping does, but reports failures
+    ///
+    /// When owning the mapping, the flinks are removed and (on unix) the os_id is unlinked.
+    /// Every cleanup step is attempted and the first error is returned.
+    pub fn close(mut self) -> Result<(), ShmemError> {
+        let mut res = Ok(());
+        if self.config.owner {
+            for flink_path in self
+                .config
+                .flink_path
+                .iter()
+                .chain(&self.config.extra_flinks)
+            {
+                if let Err(e) = remove_file(flink_path) {
+                    res = res.and(Err(ShmemError::LinkRemoveFailed(e)));
+                }
+            }
+            // Nothing left for the config to clean up on drop
+            self.config.owner = false;
+        }
+        res.and(self.mapping.close())
+    }
+    /// Returns whether the mapping was opened without write permissions
+    pub fn is_read_only(&self) -> bool {
+        self.config.read_only
+    }
     /// Returns the OS unique identifier for the mapping
+    ///
+    /// When no `os_id` was provided to `create()`, this is the randomly generated identifier
+    /// that other processes can use to `open()` the mapping
     pub fn get_os_id(&self) -> &str {
         self.mapping.unique_id.as_str()
     }
@@ -220,14 +1218,110 @@ impl Shmem {
     pub fn get_flink_path(&self) -> Option<&PathBuf> {
         self.config.flink_path.as_ref()
     }
-    /// Returns the total size of the mapping
+    /// Points the flink at `flink_path` to this mapping, replacing its previous content atomically
+    ///
+    /// Processes opening the flink concurrently either see the old or the new os_id, never a partial one.
+    /// The flink is written to a temporary file that is then renamed over `flink_path`, which is only
+    /// atomic within the same filesystem. The flink is not owned by this `Shmem` and is left in place on drop.
+    /// Mappings that cannot be opened by their os_id (anonymous, memfd or backing file) return
+    /// `ShmemError::LinkUnsupported`.
+    pub fn relink<S: AsRef<Path>>(&self, flink_path: S) -> Result<(), ShmemError> {
+        // Anonymous mappings, memfds and backing files cannot be opened through a flink
+        if self.mapping.unique_id.is_empty() || self.config.backing_file.is_some() {
+            return Err(ShmemError::LinkUnsupported);
+        }
+        #[cfg(target_os = "linux")]
+        if self.config.use_memfd {
+            return Err(ShmemError::LinkUnsupported);
+        }
+        let contents = self.config.flink_contents(&self.mapping.unique_id);
+        replace_flink(flink_path.as_ref(), &contents)
+    }
+    /// Returns the size of the mapping
+    ///
+    /// This is the size requested by the creator (rounded up to the huge page size with `use_huge_pages()`),
+    /// or the size passed to `map_subset()`. On windows, other processes only know the size of a mapping
+    /// to a page and get the same value as `mapped_size()`, unless they open it through a JSON flink.
     pub fn len(&self) -> usize {
-        self.mapping.map_size
+        self.config.size
+    }
+    /// Returns the size of the address range mapped in the current process
+    ///
+    /// The OS maps whole pages, so this is `len()` rounded up to the page size. Only the first `len()`
+    /// bytes belong to the shared object : the content of the tail is not guaranteed to be shared
+    /// with other processes nor written to a backing file.
+    pub fn mapped_size(&self) -> usize {
+        let page_size = page_size();
+        (self.mapping.map_size + page_size - 1) & !(page_size - 1)
     }
     /// Returns a raw pointer to the mapping
+    ///
+    /// There is no metadata stored in the mapping, so the pointer is always aligned to a page boundary
     pub fn as_ptr(&self) -> *mut u8 {
         self.mapping.map_ptr
     }
+    /// Grows the mapping by `additional` bytes and returns its new address
+    ///
+    /// The mapping may move in the address space of the current process, which invalidates
+    /// every pointer into it, unless it was placed with `desired_base()` : it then stays in place or
+    /// `ShmemError::BaseAddressUnavailable` is returned. Other processes keep their current view and must
+    /// `open()` the mapping again to see the new size. With `use_huge_pages()`, the size is rounded up
+    /// to the huge page size.
+    ///
+    /// Only the owner of the mapping can grow it. On windows, sections cannot be resized so a larger one
+    /// is created under the same name and the content is copied to it. This fails while other processes
+    /// still have the mapping open. Anonymous mappings and mappings opened with `map_subset()` return
+    /// `ShmemError::ResizeUnsupported`.
+    pub fn grow(&mut self, additional: usize) -> Result<*mut u8, ShmemError> {
+        if !self.is_owner() {
+            return Err(ShmemError::NotOwner);
+        }
+        if self.is_read_only() {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        // Growing from len() would truncate the part of the object that is not mapped
+        if self.mapping.map_size < self.mapping.object_size {
+            return Err(ShmemError::ResizeUnsupported);
+        }
+        let new_size = match self.len().checked_add(additional) {
+            Some(v) if v <= isize::MAX as usize => v,
+            _ => return Err(ShmemError::MapSizeTooLarge),
+        };
+
+        self.mapping
+            .grow(new_size, self.config.desired_base.is_none())?;
+        self.config.size = self.mapping.map_size;
+
+        Ok(self.as_ptr())
+    }
+    /// Synchronously writes back the whole mapping to its backing storage
+    ///
+    /// This is mostly relevant for file-backed mappings and is cheap for regular shared memory
+    pub fn flush(&self) -> Result<(), ShmemError> {
+        self.flush_range(0, self.len())
+    }
+    /// Synchronously writes back `len` bytes starting at `offset` to the mapping's backing storage
+    pub fn flush_range(&self, offset: usize, len: usize) -> Result<(), ShmemError> {
+        self.check_range(offset, len)?;
+        if len == 0 {
+            return Ok(());
+        }
+        self.mapping.flush(offset, len)
+    }
+    /// Changes the memory protection of `len` bytes starting at `offset` in the current process
+    ///
+    /// Protection applies to whole pages, so the range is extended to the page boundaries around it.
+    /// Other processes are not affected.
+    pub fn protect(&self, offset: usize, len: usize, prot: Protection) -> Result<(), ShmemError> {
+        self.check_range(offset, len)?;
+        if prot == Protection::ReadWrite && self.is_read_only() {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        self.mapping.protect(offset, len, prot)
+    }
     /// Returns mapping as a byte slice
     /// # Safety
     /// This function is unsafe because it is impossible to ensure the range of bytes is immutable
@@ -236,8 +1330,179 @@ impl Shmem {
     }
     /// Returns mapping as a mutable byte slice
     /// # Safety
-    /// This function is unsafe because it is impossible to ensure the returned mutable refence is unique/exclusive
-    pub unsafe fn as_slice_mut(&mut self) -> &mut [u8] {
-        std::slice::from_raw_parts_mut(self.as_ptr(), self.len())
+    /// This function is unsafe because it is impossible to ensure the returned mutable refence is unique/exclusive.
+    /// Mappings opened with `read_only()` return `ShmemError::WriteOnReadOnly`.
+    pub unsafe fn as_slice_mut(&mut self) -> Result<&mut [u8], ShmemError> {
+        if self.is_read_only() {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        Ok(std::slice::from_raw_parts_mut(self.as_ptr(), self.len()))
+    }
+    /// Returns mapping as a slice of `T`, ignoring trailing bytes that do not fit a whole `T`
+    /// # Safety
+    /// This function is unsafe because it is impossible to ensure the range of bytes is immutable.
+    /// `T` must be `#[repr(C)]` compatible and valid for any bit pattern found in the mapping.
+    /// Padding bytes inside `T` are not guaranteed to be preserved between processes.
+    pub unsafe fn as_typed_slice<T: Copy>(&self) -> Result<&[T], ShmemError> {
+        let num_items = self.typed_len::<T>()?;
+        Ok(std::slice::from_raw_parts(
+            self.as_ptr() as *const T,
+            num_items,
+        ))
+    }
+    /// Returns mapping as a mutable slice of `T`, ignoring trailing bytes that do not fit a whole `T`
+    /// # Safety
+    /// This function is unsafe because it is impossible to ensure the returned mutable refence is unique/exclusive.
+    /// The same requirements on `T` as `as_typed_slice()` apply.
+    pub unsafe fn as_typed_slice_mut<T: Copy>(&mut self) -> Result<&mut [T], ShmemError> {
+        if self.is_read_only() {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        let num_items = self.typed_len::<T>()?;
+        Ok(std::slice::from_raw_parts_mut(
+            self.as_ptr() as *mut T,
+            num_items,
+        ))
+    }
+    /// Copies `val` into the mapping at `offset`
+    /// # Safety
+    /// This function is unsafe because it is impossible to ensure no one else accesses the same bytes concurrently
+    pub unsafe fn write_at<T: Copy>(&self, offset: usize, val: &T) -> Result<(), ShmemError> {
+        if self.is_read_only() {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        let ptr = self.item_ptr::<T>(offset)?;
+        std::ptr::write_volatile(ptr, *val);
+        Ok(())
+    }
+    /// Returns a copy of the `T` stored in the mapping at `offset`
+    /// # Safety
+    /// This function is unsafe because it is impossible to ensure no one else writes the same bytes concurrently.
+    /// The bytes at `offset` must also be a valid `T`.
+    pub unsafe fn read_at<T: Copy>(&self, offset: usize) -> Result<T, ShmemError> {
+        let ptr = self.item_ptr::<T>(offset)?;
+        Ok(std::ptr::read_volatile(ptr))
+    }
+    /// Returns a copy of the whole mapping
+    /// # Safety
+    /// This function is unsafe because it is impossible to ensure no one else writes to the mapping concurrently
+    pub unsafe fn snapshot(&self) -> Vec<u8> {
+        self.as_slice().to_vec()
+    }
+    /// Overwrites the whole mapping with `data`, which must be exactly `len()` bytes long
+    /// # Safety
+    /// This function is unsafe because it is impossible to ensure no one else accesses the mapping concurrently
+    pub unsafe fn restore(&self, data: &[u8]) -> Result<(), ShmemError> {
+        if self.is_read_only() {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        if data.len() != self.len() {
+            return Err(ShmemError::RangeDoesNotFit);
+        }
+        std::ptr::copy_nonoverlapping(data.as_ptr(), self.as_ptr(), data.len());
+        Ok(())
+    }
+    /// Returns an `AtomicU32` stored in the mapping at `offset`
+    ///
+    /// Atomic operations on shared memory are visible to every process that maps it, as long as
+    /// they all run on the same machine (cache coherent memory and same architecture/endianness).
+    /// Read-only mappings are rejected since any store would fault.
+    /// # Safety
+    /// This function is unsafe because any access faults if the memory is made inaccessible with `protect()`
+    /// while the returned reference is in use
+    pub unsafe fn atomic_u32(&self, offset: usize) -> Result<&AtomicU32, ShmemError> {
+        self.atomic_at(offset)
+    }
+    /// Returns an `AtomicU64` stored in the mapping at `offset`
+    ///
+    /// The same requirements as `atomic_u32()` apply
+    /// # Safety
+    /// See `atomic_u32()`
+    pub unsafe fn atomic_u64(&self, offset: usize) -> Result<&AtomicU64, ShmemError> {
+        self.atomic_at(offset)
+    }
+    /// Returns an `AtomicUsize` stored in the mapping at `offset`
+    ///
+    /// The same requirements as `atomic_u32()` apply. The size of `usize` must match between processes.
+    /// # Safety
+    /// See `atomic_u32()`
+    pub unsafe fn atomic_usize(&self, offset: usize) -> Result<&AtomicUsize, ShmemError> {
+        self.atomic_at(offset)
+    }
+    /// Returns a reference to an atomic type at `offset`
+    unsafe fn atomic_at<T>(&self, offset: usize) -> Result<&T, ShmemError> {
+        if self.is_read_only() {
+            return Err(ShmemError::WriteOnReadOnly);
+        }
+        let ptr = self.item_ptr::<T>(offset)?;
+        // Atomics can be shared between threads (and processes) through shared references
+        Ok(&*ptr)
+    }
+    /// Returns a pointer to a `T` at `offset` after validating its bounds and alignment
+    fn item_ptr<T>(&self, offset: usize) -> Result<*mut T, ShmemError> {
+        self.check_range(offset, std::mem::size_of::<T>())?;
+        let ptr = unsafe { self.as_ptr().add(offset) };
+        if ptr.align_offset(std::mem::align_of::<T>()) != 0 {
+            return Err(ShmemError::AlignmentError);
+        }
+        Ok(ptr as *mut T)
+    }
+    /// Validates that `len` bytes starting at `offset` are inside the mapping
+    fn check_range(&self, offset: usize, len: usize) -> Result<(), ShmemError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.len() => Ok(()),
+            _ => Err(ShmemError::RangeDoesNotFit),
+        }
+    }
+    /// Returns how many `T` fit in the mapping after validating its alignment
+    fn typed_len<T>(&self) -> Result<usize, ShmemError> {
+        if self.as_ptr().align_offset(std::mem::align_of::<T>()) != 0 {
+            return Err(ShmemError::AlignmentError);
+        }
+        Ok(self
+            .len()
+            .checked_div(std::mem::size_of::<T>())
+            .unwrap_or(0))
+    }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+impl AsRawFd for Shmem {
+    /// Returns the file descriptor of the mapping without transferring its ownership
+    ///
+    /// Mappings from `create_anonymous()` have no file descriptor and return -1.
+    fn as_raw_fd(&self) -> RawFd {
+        self.mapping.map_fd
+    }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "linux", target_os = "macos"))]
+impl IntoRawFd for Shmem {
+    /// Returns the file descriptor of the mapping and transfers its ownership to the caller
+    ///
+    /// The memory is unmapped from the current process but the os_id and the flink are not removed.
+    /// Mappings from `create_anonymous()` have no file descriptor : -1 is returned and the memory is lost.
+    fn into_raw_fd(mut self) -> RawFd {
+        self.set_owner(false);
+        std::mem::replace(&mut self.mapping.map_fd, -1)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl AsRawHandle for Shmem {
+    /// Returns the section handle of the mapping without transferring its ownership
+    fn as_raw_handle(&self) -> RawHandle {
+        self.mapping.map_handle as _
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl IntoRawHandle for Shmem {
+    /// Returns the section handle of the mapping and transfers its ownership to the caller
+    ///
+    /// The memory is unmapped from the current process but the flink is not removed
+    fn into_raw_handle(mut self) -> RawHandle {
+        self.set_owner(false);
+        std::mem::replace(&mut self.mapping.map_handle, std::ptr::null_mut()) as _
     }
 }
@@ -1,25 +1,45 @@
 use ::nix::errno::Errno;
 use ::nix::fcntl::OFlag;
-use ::nix::sys::mman::{mmap, munmap, shm_open, shm_unlink, MapFlags, ProtFlags};
-use ::nix::sys::stat::{fstat, Mode};
-use ::nix::unistd::{close, ftruncate};
+use ::nix::sys::mman::{
+    mmap, mprotect, msync, munmap, shm_open, shm_unlink, MapFlags, MsFlags, ProtFlags,
+};
+use ::nix::sys::stat::{fchmod, fstat, Mode};
+use ::nix::unistd::{close, ftruncate, sysconf, SysconfVar};
 
-use crate::ShmemError;
+use crate::{Protection, ShmemError};
 
-use std::os::unix::io::RawFd;
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
+use std::fs::OpenOptions;
+use std::os::unix::io::{IntoRawFd, RawFd};
+use std::path::Path;
 use std::ptr::null_mut;
 
+/// Maximum length of a shared memory name (PSHMNAMLEN)
+#[cfg(target_os = "macos")]
+pub const MAX_OS_ID_LEN: usize = 31;
+/// Maximum length of a shared memory name (NAME_MAX)
+#[cfg(not(target_os = "macos"))]
+pub const MAX_OS_ID_LEN: usize = 255;
+
 pub struct MapData {
     //On linux, you must shm_unlink() the object created for the mapping. It wont disappear automatically.
     owner: bool,
+    //Whether the mapping is a named shm object (as opposed to a memfd or a raw fd)
+    named: bool,
+    //Whether the object is backed by huge pages, its size must then be a multiple of the huge page size
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    huge_pages: bool,
 
     //File descriptor to our open mapping
-    map_fd: RawFd,
+    pub map_fd: RawFd,
 
     //Shared mapping uid
     pub unique_id: String,
-    //Total size of the mapping
+    //Size of the mapped part of the object
     pub map_size: usize,
+    //Size of the whole object, larger than map_size when only its beginning is mapped
+    pub object_size: usize,
     //Pointer to the first address of our mapping
     pub map_ptr: *mut u8,
 }
@@ -32,35 +52,41 @@ impl Drop for MapData {
         if !self.map_ptr.is_null() {
             match unsafe { munmap(self.map_ptr as *mut _, self.map_size) } {
                 Ok(_) => {}
-                Err(_e) => {
-                    //debug!("os_impl::Linux : Failed to munmap() shared memory mapping...");
-                    //debug!("{}", e);
+                Err(e) => {
+                    debug!(
+                        "os_impl::Linux : Failed to munmap() shared memory mapping : {}",
+                        e
+                    );
                 }
             };
         }
 
-        //Unlink shmem
-        if self.map_fd != 0 {
+        //Unlink shmem (anonymous mappings have no fd)
+        if self.map_fd != 0 && self.map_fd != -1 {
             //unlink shmem if we created it
-            if self.owner {
+            if self.owner && self.named {
                 match shm_unlink(self.unique_id.as_str()) {
                     Ok(_) => {
-                        //debug!("shm_unlink()");
+                        trace!("shm_unlink({})", self.unique_id);
                     }
-                    Err(_e) => {
-                        //debug!("os_impl::Linux : Failed to shm_unlink() shared memory name...");
-                        //debug!("{}", e);
+                    Err(e) => {
+                        debug!(
+                            "os_impl::Linux : Failed to shm_unlink() shared memory name : {}",
+                            e
+                        );
                     }
                 };
             }
 
             match close(self.map_fd) {
                 Ok(_) => {
-                    //debug!("close()");
+                    trace!("close({})", self.map_fd);
                 }
-                Err(_e) => {
-                    //debug!("os_impl::Linux : Failed to close() shared memory file descriptor...");
-                    //debug!("{}", e);
+                Err(e) => {
+                    debug!(
+                        "os_impl::Linux : Failed to close() shared memory file descriptor : {}",
+                        e
+                    );
                 }
             };
         }
@@ -73,10 +99,287 @@ impl MapData {
         self.owner = is_owner;
         prev_val
     }
+
+    /// Does what dropping the MapData does but reports the first failure
+    ///
+    /// Every step is attempted even if a previous one failed. Dropping the MapData afterwards does nothing.
+    pub fn close(&mut self) -> Result<(), ShmemError> {
+        let mut res = Ok(());
+
+        //Unmap memory
+        if !self.map_ptr.is_null() {
+            res = res.and(
+                match unsafe { munmap(self.map_ptr as *mut _, self.map_size) } {
+                    Ok(_) => Ok(()),
+                    Err(nix::Error::Sys(e)) => Err(ShmemError::UnmapFailed(e as u32)),
+                    _ => Err(ShmemError::UnknownOsError(0xffff_ffff)),
+                },
+            );
+            self.map_ptr = null_mut();
+        }
+
+        //Unlink shmem (anonymous mappings have no fd)
+        if self.map_fd != 0 && self.map_fd != -1 {
+            if self.owner && self.named {
+                res = res.and(match shm_unlink(self.unique_id.as_str()) {
+                    Ok(_) => Ok(()),
+                    Err(nix::Error::Sys(e)) => Err(ShmemError::UnlinkFailed(e as u32)),
+                    _ => Err(ShmemError::UnknownOsError(0xffff_ffff)),
+                });
+            }
+
+            res = res.and(match close(self.map_fd) {
+                Ok(_) => Ok(()),
+                Err(nix::Error::Sys(e)) => Err(ShmemError::CloseFailed(e as u32)),
+                _ => Err(ShmemError::UnknownOsError(0xffff_ffff)),
+            });
+            self.map_fd = -1;
+        }
+
+        res
+    }
+
+    /// Grows the mapping to `new_size` bytes, moving it in our address space only if `may_move` is set
+    pub fn grow(&mut self, new_size: usize, may_move: bool) -> Result<(), ShmemError> {
+        //Anonymous mappings have no object to resize
+        if self.map_fd == -1 {
+            return Err(ShmemError::ResizeUnsupported);
+        }
+
+        //Huge page backed objects can only be resized to a multiple of the huge page size
+        #[cfg(target_os = "linux")]
+        let new_size = if self.huge_pages {
+            let page_size = huge_page_size();
+            (new_size + page_size - 1) & !(page_size - 1)
+        } else {
+            new_size
+        };
+
+        //Enlarge the memory descriptor file size first
+        match ftruncate(self.map_fd, new_size as _) {
+            Ok(_) => {}
+            Err(nix::Error::Sys(e)) => return Err(ShmemError::ResizeFailed(e as u32)),
+            _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        };
+
+        if let Err(e) = self.remap_grown(new_size, may_move) {
+            //Give the object its previous size back
+            let _ = ftruncate(self.map_fd, self.object_size as _);
+            return Err(e);
+        }
+
+        self.map_size = new_size;
+        self.object_size = new_size;
+        Ok(())
+    }
+
+    /// Extends the mapping over the grown object, Linux can do it in place or move it for us
+    #[cfg(target_os = "linux")]
+    fn remap_grown(&mut self, new_size: usize, may_move: bool) -> Result<(), ShmemError> {
+        use ::nix::sys::mman::{mremap, MRemapFlags};
+
+        //Without MREMAP_MAYMOVE, mremap() fails if the pages after the mapping are in use
+        let flags = if may_move {
+            MRemapFlags::MREMAP_MAYMOVE
+        } else {
+            MRemapFlags::empty()
+        };
+        match unsafe { mremap(self.map_ptr as *mut _, self.map_size, new_size, flags, None) } {
+            Ok(v) => {
+                self.map_ptr = v as *mut _;
+                Ok(())
+            }
+            Err(nix::Error::Sys(Errno::ENOMEM)) if !may_move => {
+                Err(ShmemError::BaseAddressUnavailable)
+            }
+            Err(nix::Error::Sys(e)) => Err(ShmemError::ResizeFailed(e as u32)),
+            _ => Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        }
+    }
+
+    /// Extends the mapping over the grown object by mapping the new pages right after it,
+    /// or by mapping the whole object again elsewhere if `may_move` is set
+    #[cfg(not(target_os = "linux"))]
+    fn remap_grown(&mut self, new_size: usize, may_move: bool) -> Result<(), ShmemError> {
+        let page_size = page_size();
+        let mapped_size = (self.map_size + page_size - 1) & !(page_size - 1);
+        if new_size <= mapped_size {
+            return Ok(());
+        }
+        let prot = ProtFlags::PROT_READ | ProtFlags::PROT_WRITE;
+
+        //Without MAP_FIXED, the address is only a hint and existing mappings are never clobbered
+        let tail = self.map_ptr.wrapping_add(mapped_size);
+        match unsafe {
+            mmap(
+                tail as *mut _,
+                new_size - mapped_size,
+                prot,
+                MapFlags::MAP_SHARED,
+                self.map_fd,
+                mapped_size as _,
+            )
+        } {
+            Ok(v) if v as *mut u8 == tail => return Ok(()),
+            Ok(v) => {
+                let _ = unsafe { munmap(v, new_size - mapped_size) };
+            }
+            Err(nix::Error::Sys(e)) => return Err(ShmemError::ResizeFailed(e as u32)),
+            _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        };
+        if !may_move {
+            return Err(ShmemError::BaseAddressUnavailable);
+        }
+
+        let new_ptr = match unsafe {
+            mmap(
+                null_mut(),
+                new_size,
+                prot,
+                MapFlags::MAP_SHARED,
+                self.map_fd,
+                0,
+            )
+        } {
+            Ok(v) => v as *mut u8,
+            Err(nix::Error::Sys(e)) => return Err(ShmemError::ResizeFailed(e as u32)),
+            _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        };
+        let _ = unsafe { munmap(self.map_ptr as *mut _, self.map_size) };
+        self.map_ptr = new_ptr;
+        Ok(())
+    }
+
+    /// Moves the mapping to `base` in our address space
+    pub fn remap_at(&mut self, base: usize, read_only: bool) -> Result<(), ShmemError> {
+        if self.map_ptr as usize == base {
+            return Ok(());
+        }
+
+        let prot = if read_only {
+            ProtFlags::PROT_READ
+        } else {
+            ProtFlags::PROT_READ | ProtFlags::PROT_WRITE
+        };
+
+        //Without MAP_FIXED, the address is only a hint and existing mappings are never clobbered
+        let new_ptr = match unsafe {
+            mmap(
+                base as *mut _,       //Desired addr
+                self.map_size,        //size of mapping
+                prot,                 //Permissions on pages
+                MapFlags::MAP_SHARED, //What kind of mapping
+                self.map_fd,          //fd
+                0,                    //Offset into fd
+            )
+        } {
+            Ok(v) => v as *mut u8,
+            Err(nix::Error::Sys(e)) => return Err(ShmemError::MapOpenFailed(e as u32)),
+            _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        };
+
+        //The OS picked another address
+        if new_ptr as usize != base {
+            let _ = unsafe { munmap(new_ptr as *mut _, self.map_size) };
+            return Err(ShmemError::BaseAddressUnavailable);
+        }
+
+        let _ = unsafe { munmap(self.map_ptr as *mut _, self.map_size) };
+        self.map_ptr = new_ptr;
+        Ok(())
+    }
+
+    /// Synchronously writes back a range of the mapping to its backing storage
+    pub fn flush(&self, offset: usize, len: usize) -> Result<(), ShmemError> {
+        //msync() requires a page aligned address
+        let start = offset & !(page_size() - 1);
+        let len = len + (offset - start);
+        match unsafe { msync(self.map_ptr.add(start) as *mut _, len, MsFlags::MS_SYNC) } {
+            Ok(_) => Ok(()),
+            Err(nix::Error::Sys(e)) => Err(ShmemError::FlushFailed(e as u32)),
+            _ => Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        }
+    }
+
+    /// Changes the protection of the pages covering a range of the mapping
+    pub fn protect(&self, offset: usize, len: usize, prot: Protection) -> Result<(), ShmemError> {
+        let page_size = page_size();
+        let start = offset & !(page_size - 1);
+        let end = (offset + len + page_size - 1) & !(page_size - 1);
+        let prot = match prot {
+            Protection::None => ProtFlags::PROT_NONE,
+            Protection::Read => ProtFlags::PROT_READ,
+            Protection::ReadWrite => ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+        };
+        match unsafe { mprotect(self.map_ptr.add(start) as *mut _, end - start, prot) } {
+            Ok(_) => Ok(()),
+            Err(nix::Error::Sys(e)) => Err(ShmemError::ProtectFailed(e as u32)),
+            _ => Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        }
+    }
+}
+
+/// Returns the uids of the named shm objects starting with `prefix`
+#[cfg(target_os = "linux")]
+pub fn list_mappings(prefix: &str) -> Result<Vec<String>, ShmemError> {
+    //Named shm objects are files in /dev/shm, without the leading '/'
+    let prefix = prefix.trim_start_matches('/');
+    let entries = match std::fs::read_dir("/dev/shm") {
+        Ok(v) => v,
+        Err(e) => return Err(ShmemError::ListFailed(e)),
+    };
+
+    let mut unique_ids = Vec::new();
+    for entry in entries {
+        let entry = match entry {
+            Ok(v) => v,
+            Err(e) => return Err(ShmemError::ListFailed(e)),
+        };
+        if let Some(name) = entry.file_name().to_str() {
+            if name.starts_with(prefix) {
+                unique_ids.push(format!("/{}", name));
+            }
+        }
+    }
+    Ok(unique_ids)
+}
+
+/// Other unixes do not expose their named shm objects
+#[cfg(not(target_os = "linux"))]
+pub fn list_mappings(_prefix: &str) -> Result<Vec<String>, ShmemError> {
+    Err(ShmemError::ListUnsupported)
+}
+
+/// Converts the errno of a failed creation into the most specific error
+fn create_error(e: Errno) -> ShmemError {
+    match e {
+        Errno::EEXIST => ShmemError::MappingIdExists,
+        Errno::ENOMEM | Errno::ENOSPC => ShmemError::OutOfMemory,
+        Errno::EACCES | Errno::EPERM => ShmemError::PermissionDenied,
+        e => ShmemError::MapCreateFailed(e as u32),
+    }
+}
+
+/// Returns the size of a memory page
+pub fn page_size() -> usize {
+    match sysconf(SysconfVar::PAGE_SIZE) {
+        Ok(Some(v)) => v as usize,
+        _ => 4096,
+    }
 }
 
 /// Creates a mapping specified by the uid and size
-pub fn create_mapping(unique_id: &str, map_size: usize) -> Result<MapData, ShmemError> {
+pub fn create_mapping(
+    unique_id: &str,
+    map_size: usize,
+    huge_pages: bool,
+    mode: Option<u32>,
+) -> Result<MapData, ShmemError> {
+    //Named shm objects live on a tmpfs which cannot provide huge pages
+    if huge_pages {
+        return Err(ShmemError::HugePagesUnavailable);
+    }
+
     //Create shared memory file descriptor
     let shmem_fd = match shm_open(
         unique_id, //Unique name that usualy pops up in /dev/shm/
@@ -84,19 +387,175 @@ pub fn create_mapping(unique_id: &str, map_size: usize) -> Result<MapData, Shmem
         Mode::S_IRUSR | Mode::S_IWUSR,                  //Permission allow user+rw
     ) {
         Ok(v) => v,
-        Err(nix::Error::Sys(Errno::EEXIST)) => return Err(ShmemError::MappingIdExists),
-        Err(nix::Error::Sys(e)) => return Err(ShmemError::MapCreateFailed(e as u32)),
+        Err(nix::Error::Sys(e)) => return Err(create_error(e)),
         _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
     };
 
     let mut new_map: MapData = MapData {
         owner: true,
+        named: true,
+        huge_pages: false,
         unique_id: String::from(unique_id),
         map_fd: shmem_fd,
         map_size,
+        object_size: map_size,
+        map_ptr: null_mut(),
+    };
+
+    //Apply the requested permissions, the mode given to shm_open() is filtered by the umask
+    if let Some(mode) = mode {
+        match fchmod(new_map.map_fd, Mode::from_bits_truncate(mode as _)) {
+            Ok(_) => {}
+            Err(nix::Error::Sys(e)) => return Err(create_error(e)),
+            _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
+        };
+    }
+
+    resize_and_map(&mut new_map)?;
+
+    Ok(new_map)
+}
+
+/// Creates a mapping backed by an anonymous memfd, the uid is only used as a label
+#[cfg(target_os = "linux")]
+pub fn create_memfd_mapping(
+    unique_id: &str,
+    map_size: usize,
+    huge_pages: bool,
+) -> Result<MapData, ShmemError> {
+    use ::nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+
+    let name = match CString::new(unique_id) {
+        Ok(v) => v,
+        Err(_) => return Err(ShmemError::MapCreateFailed(Errno::EINVAL as u32)),
+    };
+
+    let (flags, map_size) = if huge_pages {
+        let page_size = huge_page_size();
+        (
+            MemFdCreateFlag::MFD_CLOEXEC | MemFdCreateFlag::from_bits_truncate(libc::MFD_HUGETLB),
+            (map_size + page_size - 1) & !(page_size - 1),
+        )
+    } else {
+        (MemFdCreateFlag::MFD_CLOEXEC, map_size)
+    };
+
+    //Create anonymous memory file descriptor
+    let memfd = match memfd_create(&name, flags) {
+        Ok(v) => v,
+        Err(nix::Error::Sys(Errno::ENOSYS)) => return Err(ShmemError::MemfdUnsupported),
+        Err(nix::Error::Sys(Errno::EINVAL)) if huge_pages => {
+            return Err(ShmemError::HugePagesUnavailable)
+        }
+        Err(nix::Error::Sys(e)) => return Err(create_error(e)),
+        _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
+    };
+
+    let mut new_map: MapData = MapData {
+        owner: true,
+        named: false,
+        huge_pages,
+        unique_id: String::from(unique_id),
+        map_fd: memfd,
+        map_size,
+        object_size: map_size,
         map_ptr: null_mut(),
     };
 
+    //Failing to reserve the pages means the system has no huge pages available
+    match resize_and_map(&mut new_map) {
+        Err(_) if huge_pages => return Err(ShmemError::HugePagesUnavailable),
+        res => res?,
+    };
+
+    Ok(new_map)
+}
+
+/// Returns the default huge page size of the system
+#[cfg(target_os = "linux")]
+fn huge_page_size() -> usize {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    meminfo
+        .lines()
+        .find(|l| l.starts_with("Hugepagesize:"))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<usize>().ok())
+        .map(|kb| kb * 1024)
+        .unwrap_or(2 * 1024 * 1024)
+}
+
+/// Creates a mapping backed by a new file on disk
+pub fn create_file_mapping(path: &Path, map_size: usize) -> Result<MapData, ShmemError> {
+    let file = match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(path)
+    {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(match e.kind() {
+                std::io::ErrorKind::AlreadyExists => ShmemError::MappingIdExists,
+                std::io::ErrorKind::PermissionDenied => ShmemError::PermissionDenied,
+                _ => ShmemError::MapCreateFailed(e.raw_os_error().unwrap_or(0) as u32),
+            })
+        }
+    };
+
+    //The file is persistent and never unlinked, even by the owner
+    let mut new_map: MapData = MapData {
+        owner: true,
+        named: false,
+        huge_pages: false,
+        unique_id: path.to_string_lossy().into_owned(),
+        map_fd: file.into_raw_fd(),
+        map_size,
+        object_size: map_size,
+        map_ptr: null_mut(),
+    };
+
+    //Do not leave an unusable file behind
+    if let Err(e) = resize_and_map(&mut new_map) {
+        let _ = std::fs::remove_file(path);
+        return Err(e);
+    }
+
+    Ok(new_map)
+}
+
+/// Creates an anonymous mapping that is only shared with children created by fork()
+pub fn create_anonymous_mapping(map_size: usize) -> Result<MapData, ShmemError> {
+    let mut new_map: MapData = MapData {
+        owner: true,
+        named: false,
+        huge_pages: false,
+        unique_id: String::new(),
+        map_fd: -1,
+        map_size,
+        object_size: map_size,
+        map_ptr: null_mut(),
+    };
+
+    new_map.map_ptr = match unsafe {
+        mmap(
+            null_mut(),                                     //Desired addr
+            new_map.map_size,                               //size of mapping
+            ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,   //Permissions on pages
+            MapFlags::MAP_SHARED | MapFlags::MAP_ANONYMOUS, //Shared with children only
+            -1,                                             //No fd
+            0,                                              //Offset into fd
+        )
+    } {
+        Ok(v) => v as *mut _,
+        Err(nix::Error::Sys(e)) => return Err(create_error(e)),
+        _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
+    };
+
+    Ok(new_map)
+}
+
+/// Sets the size of a newly created mapping and maps it read/write
+fn resize_and_map(new_map: &mut MapData) -> Result<(), ShmemError> {
     //Enlarge the memory descriptor file size to the requested map size
     match ftruncate(new_map.map_fd, new_map.map_size as _) {
         Ok(_) => {}
@@ -116,50 +575,173 @@ pub fn create_mapping(unique_id: &str, map_size: usize) -> Result<MapData, Shmem
         )
     } {
         Ok(v) => v as *mut _,
-        Err(nix::Error::Sys(e)) => return Err(ShmemError::MapCreateFailed(e as u32)),
+        Err(nix::Error::Sys(e)) => return Err(create_error(e)),
         _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
     };
 
-    Ok(new_map)
+    Ok(())
+}
+
+/// Unlinks the named shm object without needing a handle to it
+pub fn remove_mapping(unique_id: &str) -> Result<(), ShmemError> {
+    match shm_unlink(unique_id) {
+        Ok(_) => Ok(()),
+        Err(nix::Error::Sys(Errno::ENOENT)) => Err(ShmemError::MappingIdDoesNotExist),
+        Err(nix::Error::Sys(e)) => Err(ShmemError::UnknownOsError(e as u32)),
+        _ => Err(ShmemError::UnknownOsError(0xffff_ffff)),
+    }
+}
+
+/// Returns whether a mapping with this uid exists, without creating or mapping it
+pub fn mapping_exists(unique_id: &str) -> bool {
+    match shm_open(unique_id, OFlag::O_RDONLY, Mode::empty()) {
+        Ok(fd) => {
+            let _ = close(fd);
+            true
+        }
+        //Someone else's mapping
+        Err(nix::Error::Sys(Errno::EACCES)) => true,
+        _ => false,
+    }
 }
 
 /// Opens an existing mapping specified by its uid
-pub fn open_mapping(unique_id: &str) -> Result<MapData, ShmemError> {
+pub fn open_mapping(
+    unique_id: &str,
+    read_only: bool,
+    max_size: Option<usize>,
+) -> Result<MapData, ShmemError> {
+    let oflag = if read_only {
+        OFlag::O_RDONLY
+    } else {
+        OFlag::O_RDWR
+    };
+
     //Open shared memory
     let shmem_fd = match shm_open(
         unique_id,
-        OFlag::O_RDWR, //Open read write
+        oflag, //Open read only or read write
         Mode::S_IRUSR,
     ) {
         Ok(v) => v,
+        Err(nix::Error::Sys(Errno::ENOENT)) => return Err(ShmemError::MappingIdDoesNotExist),
         Err(nix::Error::Sys(e)) => return Err(ShmemError::MapOpenFailed(e as u32)),
         _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
     };
 
     let mut new_map: MapData = MapData {
         owner: false,
+        named: true,
+        huge_pages: false,
         unique_id: String::from(unique_id),
         map_fd: shmem_fd,
         map_size: 0,
+        object_size: 0,
+        map_ptr: null_mut(),
+    };
+
+    map_existing(&mut new_map, read_only, max_size)?;
+
+    Ok(new_map)
+}
+
+/// Opens an existing mapping backed by a file on disk
+pub fn open_file_mapping(
+    path: &Path,
+    read_only: bool,
+    max_size: Option<usize>,
+) -> Result<MapData, ShmemError> {
+    let file = match OpenOptions::new().read(true).write(!read_only).open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ShmemError::MappingIdDoesNotExist)
+        }
+        Err(e) => {
+            return Err(ShmemError::MapOpenFailed(
+                e.raw_os_error().unwrap_or(0) as u32
+            ))
+        }
+    };
+
+    let mut new_map: MapData = MapData {
+        owner: false,
+        named: false,
+        huge_pages: false,
+        unique_id: path.to_string_lossy().into_owned(),
+        map_fd: file.into_raw_fd(),
+        map_size: 0,
+        object_size: 0,
+        map_ptr: null_mut(),
+    };
+
+    map_existing(&mut new_map, read_only, max_size)?;
+
+    Ok(new_map)
+}
+
+/// Maps an existing mapping from a file descriptor, taking ownership of the fd
+pub fn from_raw_fd(
+    fd: RawFd,
+    read_only: bool,
+    max_size: Option<usize>,
+) -> Result<MapData, ShmemError> {
+    let mut new_map: MapData = MapData {
+        owner: false,
+        named: false,
+        huge_pages: false,
+        unique_id: String::new(),
+        map_fd: fd,
+        map_size: 0,
+        object_size: 0,
         map_ptr: null_mut(),
     };
 
-    //Get mmap size
-    new_map.map_size = match fstat(new_map.map_fd) {
+    map_existing(&mut new_map, read_only, max_size)?;
+
+    Ok(new_map)
+}
+
+/// Maps the whole content of an already opened mapping
+fn map_existing(
+    new_map: &mut MapData,
+    read_only: bool,
+    max_size: Option<usize>,
+) -> Result<(), ShmemError> {
+    let prot = if read_only {
+        ProtFlags::PROT_READ
+    } else {
+        ProtFlags::PROT_READ | ProtFlags::PROT_WRITE
+    };
+
+    //Get the size of the object
+    new_map.object_size = match fstat(new_map.map_fd) {
         Ok(v) => v.st_size as usize,
         Err(nix::Error::Sys(e)) => return Err(ShmemError::MapOpenFailed(e as u32)),
         _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
     };
+    //The creator has not set the size yet
+    if new_map.object_size == 0 {
+        return Err(ShmemError::MapSizeZero);
+    }
+
+    //Only map the beginning of the object if requested
+    new_map.map_size = match max_size {
+        Some(v) if v > new_map.object_size => {
+            return Err(ShmemError::MapTooSmall(new_map.object_size, v))
+        }
+        Some(v) => v,
+        None => new_map.object_size,
+    };
 
     //Map memory into our address space
     new_map.map_ptr = match unsafe {
         mmap(
-            null_mut(),                                   //Desired addr
-            new_map.map_size,                             //size of mapping
-            ProtFlags::PROT_READ | ProtFlags::PROT_WRITE, //Permissions on pages
-            MapFlags::MAP_SHARED,                         //What kind of mapping
-            new_map.map_fd,                               //fd
-            0,                                            //Offset into fd
+            null_mut(),           //Desired addr
+            new_map.map_size,     //size of mapping
+            prot,                 //Permissions on pages
+            MapFlags::MAP_SHARED, //What kind of mapping
+            new_map.map_fd,       //fd
+            0,                    //Offset into fd
         )
     } {
         Ok(v) => v as *mut _,
@@ -167,5 +749,5 @@ pub fn open_mapping(unique_id: &str) -> Result<MapData, ShmemError> {
         _ => return Err(ShmemError::UnknownOsError(0xffff_ffff)),
     };
 
-    Ok(new_map)
+    Ok(())
 }
@@ -1,33 +1,63 @@
 use ::winapi::{
     shared::{
         ntdef::{FALSE, NULL},
-        winerror::ERROR_ALREADY_EXISTS,
+        sddl::{ConvertStringSecurityDescriptorToSecurityDescriptorA, SDDL_REVISION_1},
+        winerror::{
+            ERROR_ACCESS_DENIED, ERROR_ALREADY_EXISTS, ERROR_COMMITMENT_LIMIT,
+            ERROR_FILE_NOT_FOUND, ERROR_INVALID_ADDRESS, ERROR_INVALID_PARAMETER,
+            ERROR_NOT_ENOUGH_MEMORY, ERROR_OUTOFMEMORY, ERROR_PRIVILEGE_NOT_HELD,
+        },
     },
     um::{
         errhandlingapi::GetLastError,
+        fileapi::FlushFileBuffers,
         handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
-        memoryapi::{MapViewOfFile, UnmapViewOfFile, VirtualQuery, FILE_MAP_READ, FILE_MAP_WRITE},
-        winbase::{CreateFileMappingA, OpenFileMappingA},
-        winnt::{HANDLE, MEMORY_BASIC_INFORMATION, PAGE_READWRITE},
+        memoryapi::{
+            FlushViewOfFile, GetLargePageMinimum, MapViewOfFile, MapViewOfFileEx, UnmapViewOfFile,
+            VirtualProtect, VirtualQuery, FILE_MAP_LARGE_PAGES, FILE_MAP_READ, FILE_MAP_WRITE,
+        },
+        minwinbase::SECURITY_ATTRIBUTES,
+        sysinfoapi::{GetSystemInfo, SYSTEM_INFO},
+        winbase::{CreateFileMappingA, LocalFree, OpenFileMappingA},
+        winnt::{
+            HANDLE, MEMORY_BASIC_INFORMATION, PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE,
+            SEC_COMMIT, SEC_LARGE_PAGES,
+        },
     },
 };
 
-use crate::ShmemError;
+use crate::{Protection, ShmemError};
 
 use std::ffi::CString;
-use std::mem::size_of;
-use std::ptr::null_mut;
+use std::fs::OpenOptions;
+use std::mem::{size_of, zeroed};
+use std::os::windows::io::IntoRawHandle;
+use std::path::Path;
+use std::ptr::{null, null_mut};
+
+/// Maximum length of a file mapping object name (MAX_PATH)
+pub const MAX_OS_ID_LEN: usize = 260;
 
 pub struct MapData {
     ///The handle to our open mapping
-    map_handle: HANDLE,
+    pub map_handle: HANDLE,
+    ///The handle to the backing file of the mapping if any
+    file_handle: HANDLE,
 
     //Shared mapping uid
     pub unique_id: String,
-    //Total size of the mapping
+    //Size of the mapped part of the section
     pub map_size: usize,
+    //Size of the whole section, larger than map_size when only its beginning is mapped
+    pub object_size: usize,
     //Pointer to the first byte of our mapping
     pub map_ptr: *mut u8,
+
+    //Protection and access the section was created and mapped with, used to recreate it
+    page_protect: u32,
+    map_access: u32,
+    //Security descriptor the section was created with
+    security_descriptor: Option<String>,
 }
 ///Teardown UnmapViewOfFile and close CreateMapping handle
 impl Drop for MapData {
@@ -35,78 +65,534 @@ impl Drop for MapData {
     fn drop(&mut self) {
         //Unmap memory from our process
         if self.map_ptr as *mut _ != NULL {
-            unsafe {
-                UnmapViewOfFile(self.map_ptr as *mut _);
+            if unsafe { UnmapViewOfFile(self.map_ptr as *mut _) } == 0 {
+                debug!(
+                    "os_impl::Windows : Failed to UnmapViewOfFile() shared memory mapping : {}",
+                    unsafe { GetLastError() }
+                );
+            } else {
+                trace!("UnmapViewOfFile({:p})", self.map_ptr);
             }
         }
 
         //Close our mapping
         if self.map_handle as *mut _ != NULL {
-            unsafe {
-                CloseHandle(self.map_handle);
+            if unsafe { CloseHandle(self.map_handle) } == 0 {
+                debug!(
+                    "os_impl::Windows : Failed to CloseHandle() shared memory mapping : {}",
+                    unsafe { GetLastError() }
+                );
+            } else {
+                trace!("CloseHandle({:p})", self.map_handle);
+            }
+        }
+
+        //Close the backing file
+        if self.file_handle as *mut _ != NULL {
+            if unsafe { CloseHandle(self.file_handle) } == 0 {
+                debug!(
+                    "os_impl::Windows : Failed to CloseHandle() backing file : {}",
+                    unsafe { GetLastError() }
+                );
+            } else {
+                trace!("CloseHandle({:p})", self.file_handle);
             }
         }
     }
 }
 
+impl MapData {
+    //Does what dropping the MapData does but reports the first failure
+    //Every step is attempted even if a previous one failed. Dropping the MapData afterwards does nothing.
+    pub fn close(&mut self) -> Result<(), ShmemError> {
+        let mut res = Ok(());
+
+        //Unmap memory from our process
+        if self.map_ptr as *mut _ != NULL {
+            if unsafe { UnmapViewOfFile(self.map_ptr as *mut _) } == 0 {
+                res = res.and(Err(ShmemError::UnmapFailed(unsafe { GetLastError() })));
+            }
+            self.map_ptr = null_mut();
+        }
+
+        //Close our mapping
+        if self.map_handle as *mut _ != NULL {
+            if unsafe { CloseHandle(self.map_handle) } == 0 {
+                res = res.and(Err(ShmemError::CloseFailed(unsafe { GetLastError() })));
+            }
+            self.map_handle = NULL;
+        }
+
+        //Close the backing file
+        if self.file_handle as *mut _ != NULL {
+            if unsafe { CloseHandle(self.file_handle) } == 0 {
+                res = res.and(Err(ShmemError::CloseFailed(unsafe { GetLastError() })));
+            }
+            self.file_handle = NULL;
+        }
+
+        res
+    }
+
+    /// Moves the mapping to `base` in our address space
+    pub fn remap_at(&mut self, base: usize, read_only: bool) -> Result<(), ShmemError> {
+        if self.map_ptr as usize == base {
+            return Ok(());
+        }
+
+        let map_access = if read_only {
+            FILE_MAP_READ
+        } else {
+            FILE_MAP_READ | FILE_MAP_WRITE
+        };
+
+        //Map a second view at the requested address before releasing the current one
+        let new_ptr: *mut u8 = unsafe {
+            MapViewOfFileEx(
+                self.map_handle,
+                map_access,
+                0,
+                0,
+                self.map_size,
+                base as *mut _,
+            )
+        } as _;
+        if new_ptr.is_null() {
+            let last_error = unsafe { GetLastError() };
+            return Err(match last_error {
+                ERROR_INVALID_ADDRESS => ShmemError::BaseAddressUnavailable,
+                _ => ShmemError::MapOpenFailed(last_error),
+            });
+        }
+
+        unsafe { UnmapViewOfFile(self.map_ptr as *mut _) };
+        self.map_ptr = new_ptr;
+        Ok(())
+    }
+
+    /// Writes back a range of the mapping to its backing storage
+    pub fn flush(&self, offset: usize, len: usize) -> Result<(), ShmemError> {
+        if unsafe { FlushViewOfFile(self.map_ptr.add(offset) as *const _, len) } == 0 {
+            let last_error = unsafe { GetLastError() };
+            return Err(ShmemError::FlushFailed(last_error));
+        }
+        //Dirty pages are only written back lazily unless the file is flushed as well
+        if self.file_handle as *mut _ != NULL && unsafe { FlushFileBuffers(self.file_handle) } == 0
+        {
+            let last_error = unsafe { GetLastError() };
+            return Err(ShmemError::FlushFailed(last_error));
+        }
+        Ok(())
+    }
+
+    //Grows the mapping to `new_size` bytes, moving it in our address space only if `may_move` is set
+    //Sections cannot be resized, so a larger one is created under the same name and the content is copied over.
+    //This fails with ERROR_ALREADY_EXISTS while other processes still have the section open.
+    pub fn grow(&mut self, new_size: usize, may_move: bool) -> Result<(), ShmemError> {
+        //Unnamed sections are shared through their handle, a new section would not be shared anymore
+        if self.unique_id.is_empty() && self.file_handle as *mut _ == NULL {
+            return Err(ShmemError::ResizeUnsupported);
+        }
+
+        //Large page sections are sized to a multiple of the large page size
+        let new_size = if self.page_protect & SEC_LARGE_PAGES != 0 {
+            let page_size = unsafe { GetLargePageMinimum() };
+            (new_size + page_size - 1) & !(page_size - 1)
+        } else {
+            new_size
+        };
+
+        //The content of file backed sections stays in the file, which the new section extends
+        if self.file_handle as *mut _ != NULL {
+            let (new_handle, last_error) = create_section(
+                self.file_handle,
+                "",
+                new_size,
+                self.page_protect,
+                None,
+                false,
+            )?;
+            if new_handle == NULL {
+                return Err(ShmemError::ResizeFailed(last_error));
+            }
+            if let Err(e) = self.swap_view(new_handle, new_size, may_move) {
+                unsafe { CloseHandle(new_handle) };
+                return Err(e);
+            }
+            return Ok(());
+        }
+
+        //Keep a copy of the content, the section is destroyed when our handle and view are released
+        let old_ptr = self.map_ptr;
+        let content = unsafe { std::slice::from_raw_parts(self.map_ptr, self.map_size) }.to_vec();
+        unsafe {
+            UnmapViewOfFile(self.map_ptr as *mut _);
+            CloseHandle(self.map_handle);
+        }
+        self.map_ptr = null_mut();
+        self.map_handle = NULL;
+
+        let res = self.recreate_section(new_size, old_ptr, may_move);
+        //Another process kept the section alive, it is mapped again untouched
+        if let Err(ShmemError::ResizeFailed(ERROR_ALREADY_EXISTS)) = res {
+            return res;
+        }
+        //Bring the section back with its previous size
+        if res.is_err() {
+            let _ = self.recreate_section(content.len(), old_ptr, may_move);
+        }
+
+        //The new section is zeroed
+        if !self.map_ptr.is_null() {
+            unsafe { std::ptr::copy_nonoverlapping(content.as_ptr(), self.map_ptr, content.len()) };
+        }
+
+        res
+    }
+
+    //Creates the pagefile backed section again with `new_size` bytes and maps it at `base`,
+    //or anywhere if `may_move` is set and `base` is unavailable
+    fn recreate_section(
+        &mut self,
+        new_size: usize,
+        base: *mut u8,
+        may_move: bool,
+    ) -> Result<(), ShmemError> {
+        let (map_handle, last_error) = create_section(
+            INVALID_HANDLE_VALUE,
+            &self.unique_id,
+            new_size,
+            self.page_protect,
+            self.security_descriptor.as_deref(),
+            false,
+        )?;
+        if map_handle == NULL {
+            return Err(ShmemError::ResizeFailed(last_error));
+        }
+        self.map_handle = map_handle;
+
+        let mut map_ptr: *mut u8 =
+            unsafe { MapViewOfFileEx(map_handle, self.map_access, 0, 0, 0, base as *mut _) } as _;
+        if map_ptr.is_null() && may_move {
+            map_ptr = unsafe { MapViewOfFile(map_handle, self.map_access, 0, 0, 0) } as _;
+        }
+        if map_ptr.is_null() {
+            let map_error = unsafe { GetLastError() };
+            unsafe { CloseHandle(map_handle) };
+            self.map_handle = NULL;
+            return Err(match map_error {
+                ERROR_INVALID_ADDRESS => ShmemError::BaseAddressUnavailable,
+                e => ShmemError::ResizeFailed(e),
+            });
+        }
+        self.map_ptr = map_ptr;
+
+        //The section we got is the previous one, with its previous size
+        if last_error == ERROR_ALREADY_EXISTS {
+            return Err(ShmemError::ResizeFailed(ERROR_ALREADY_EXISTS));
+        }
+
+        self.map_size = new_size;
+        self.object_size = new_size;
+        Ok(())
+    }
+
+    //Replaces our section and view by a view of `new_handle`, at the same address unless `may_move` is set
+    fn swap_view(
+        &mut self,
+        new_handle: HANDLE,
+        new_size: usize,
+        may_move: bool,
+    ) -> Result<(), ShmemError> {
+        let old_ptr = self.map_ptr;
+        //The current view must be released to free its address
+        if !may_move {
+            unsafe { UnmapViewOfFile(old_ptr as *mut _) };
+        }
+
+        let base = if may_move { null_mut() } else { old_ptr };
+        let new_ptr: *mut u8 =
+            unsafe { MapViewOfFileEx(new_handle, self.map_access, 0, 0, new_size, base as *mut _) }
+                as _;
+        if new_ptr.is_null() {
+            let last_error = unsafe { GetLastError() };
+            //Put the current view back where it was
+            if !may_move {
+                self.map_ptr = unsafe {
+                    MapViewOfFileEx(
+                        self.map_handle,
+                        self.map_access,
+                        0,
+                        0,
+                        self.map_size,
+                        old_ptr as *mut _,
+                    )
+                } as _;
+            }
+            return Err(match last_error {
+                ERROR_INVALID_ADDRESS => ShmemError::BaseAddressUnavailable,
+                e => ShmemError::ResizeFailed(e),
+            });
+        }
+
+        if may_move {
+            unsafe { UnmapViewOfFile(old_ptr as *mut _) };
+        }
+        unsafe { CloseHandle(self.map_handle) };
+        self.map_handle = new_handle;
+        self.map_ptr = new_ptr;
+        self.map_size = new_size;
+        self.object_size = new_size;
+        Ok(())
+    }
+
+    /// Changes the protection of the pages covering a range of the mapping
+    pub fn protect(&self, offset: usize, len: usize, prot: Protection) -> Result<(), ShmemError> {
+        let prot = match prot {
+            Protection::None => PAGE_NOACCESS,
+            Protection::Read => PAGE_READONLY,
+            Protection::ReadWrite => PAGE_READWRITE,
+        };
+        let mut old_prot = 0;
+        if unsafe { VirtualProtect(self.map_ptr.add(offset) as *mut _, len, prot, &mut old_prot) }
+            == 0
+        {
+            let last_error = unsafe { GetLastError() };
+            return Err(ShmemError::ProtectFailed(last_error));
+        }
+        Ok(())
+    }
+}
+
+//Enumerating the object namespace requires undocumented NT APIs
+pub fn list_mappings(_prefix: &str) -> Result<Vec<String>, ShmemError> {
+    Err(ShmemError::ListUnsupported)
+}
+
+//Converts the error of a failed creation into the most specific error
+fn create_error(last_error: u32) -> ShmemError {
+    match last_error {
+        ERROR_ALREADY_EXISTS => ShmemError::MappingIdExists,
+        ERROR_NOT_ENOUGH_MEMORY | ERROR_OUTOFMEMORY | ERROR_COMMITMENT_LIMIT => {
+            ShmemError::OutOfMemory
+        }
+        ERROR_ACCESS_DENIED => ShmemError::PermissionDenied,
+        e => ShmemError::MapCreateFailed(e),
+    }
+}
+
+//Returns the size of a memory page
+pub fn page_size() -> usize {
+    let mut info: SYSTEM_INFO = unsafe { zeroed() };
+    unsafe { GetSystemInfo(&mut info) };
+    info.dwPageSize as usize
+}
+
 //Creates a mapping specified by the uid and size
-pub fn create_mapping(unique_id: &str, map_size: usize) -> Result<MapData, ShmemError> {
+pub fn create_mapping(
+    unique_id: &str,
+    map_size: usize,
+    huge_pages: bool,
+    security_descriptor: Option<&str>,
+) -> Result<MapData, ShmemError> {
+    //Large page sections must be committed up front and sized to a multiple of the large page size
+    let (page_protect, map_access, map_size) = if huge_pages {
+        let page_size = unsafe { GetLargePageMinimum() };
+        if page_size == 0 {
+            return Err(ShmemError::HugePagesUnavailable);
+        }
+        (
+            PAGE_READWRITE | SEC_COMMIT | SEC_LARGE_PAGES,
+            FILE_MAP_READ | FILE_MAP_WRITE | FILE_MAP_LARGE_PAGES,
+            (map_size + page_size - 1) & !(page_size - 1),
+        )
+    } else {
+        (PAGE_READWRITE, FILE_MAP_READ | FILE_MAP_WRITE, map_size)
+    };
+
     let mut new_map: MapData = MapData {
         unique_id: String::from(unique_id),
         map_handle: NULL,
+        file_handle: NULL,
         map_size,
+        object_size: map_size,
         map_ptr: null_mut(),
+        page_protect,
+        map_access,
+        security_descriptor: security_descriptor.map(String::from),
     };
 
     //Create Mapping
-    new_map.map_handle = unsafe {
-        let high_size: u32 = (map_size as u64 & 0xFFFF_FFFF_0000_0000 as u64) as u32;
-        let low_size: u32 = (map_size as u64 & 0xFFFF_FFFF as u64) as u32;
-        CreateFileMappingA(
-            INVALID_HANDLE_VALUE,
-            null_mut(),
-            PAGE_READWRITE,
-            high_size,
-            low_size,
-            #[allow(clippy::temporary_cstring_as_ptr)]
-            CString::new(unique_id).unwrap().as_ptr(),
-        )
-    };
-    let last_error = unsafe { GetLastError() };
+    let (map_handle, last_error) = create_section(
+        INVALID_HANDLE_VALUE,
+        unique_id,
+        map_size,
+        page_protect,
+        security_descriptor,
+        false,
+    )?;
+    new_map.map_handle = map_handle;
 
     if new_map.map_handle == NULL {
-        return Err(ShmemError::MapCreateFailed(last_error));
+        if huge_pages && last_error == ERROR_PRIVILEGE_NOT_HELD {
+            return Err(ShmemError::HugePagesUnavailable);
+        }
+        if last_error == ERROR_ACCESS_DENIED && unique_id.starts_with("Global\\") {
+            return Err(ShmemError::GlobalNamespaceDenied);
+        }
+        return Err(create_error(last_error));
     } else if last_error == ERROR_ALREADY_EXISTS {
         return Err(ShmemError::MappingIdExists);
     }
 
     //Map mapping into address space
-    new_map.map_ptr =
-        unsafe { MapViewOfFile(new_map.map_handle, FILE_MAP_READ | FILE_MAP_WRITE, 0, 0, 0) } as _;
+    new_map.map_ptr = unsafe { MapViewOfFile(new_map.map_handle, map_access, 0, 0, 0) } as _;
     if new_map.map_ptr.is_null() {
         let last_error = unsafe { GetLastError() };
         unsafe {
             CloseHandle(new_map.map_handle);
         }
-        return Err(ShmemError::MapCreateFailed(last_error));
+        return Err(create_error(last_error));
     }
 
     Ok(new_map)
 }
 
+//Creates a section of `map_size` bytes over `file` (INVALID_HANDLE_VALUE for the pagefile)
+//Returns the handle along with the last error, which is ERROR_ALREADY_EXISTS when the uid was already in use
+fn create_section(
+    file: HANDLE,
+    unique_id: &str,
+    map_size: usize,
+    page_protect: u32,
+    security_descriptor: Option<&str>,
+    inheritable: bool,
+) -> Result<(HANDLE, u32), ShmemError> {
+    //Build the security attributes from the SDDL string
+    let mut security_attributes = SECURITY_ATTRIBUTES {
+        nLength: size_of::<SECURITY_ATTRIBUTES>() as _,
+        lpSecurityDescriptor: null_mut(),
+        bInheritHandle: inheritable as _,
+    };
+    if let Some(sddl) = security_descriptor {
+        let sddl = match CString::new(sddl) {
+            Ok(v) => v,
+            Err(_) => return Err(ShmemError::MapCreateFailed(ERROR_INVALID_PARAMETER)),
+        };
+        let ret_val = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorA(
+                sddl.as_ptr(),
+                SDDL_REVISION_1 as _,
+                &mut security_attributes.lpSecurityDescriptor,
+                null_mut(),
+            )
+        };
+        if ret_val == 0 {
+            let last_error = unsafe { GetLastError() };
+            return Err(ShmemError::MapCreateFailed(last_error));
+        }
+    }
+
+    //Unnamed sections have no uid
+    let name = match CString::new(unique_id) {
+        Ok(v) => v,
+        Err(_) => return Err(ShmemError::MapCreateFailed(ERROR_INVALID_PARAMETER)),
+    };
+    let map_handle = unsafe {
+        CreateFileMappingA(
+            file,
+            if security_descriptor.is_some() || inheritable {
+                &mut security_attributes as *mut _
+            } else {
+                null_mut()
+            },
+            page_protect,
+            (map_size as u64 >> 32) as u32,
+            (map_size as u64 & 0xFFFF_FFFF) as u32,
+            if unique_id.is_empty() {
+                null()
+            } else {
+                name.as_ptr()
+            },
+        )
+    };
+    let last_error = unsafe { GetLastError() };
+    trace!(
+        "CreateFileMappingA({}, {} bytes) : {:p}, last error {}",
+        unique_id,
+        map_size,
+        map_handle,
+        last_error
+    );
+
+    if !security_attributes.lpSecurityDescriptor.is_null() {
+        unsafe {
+            LocalFree(security_attributes.lpSecurityDescriptor);
+        }
+    }
+
+    Ok((map_handle, last_error))
+}
+
+//Named sections are destroyed when their last handle is closed, there is nothing to remove
+pub fn remove_mapping(unique_id: &str) -> Result<(), ShmemError> {
+    if mapping_exists(unique_id) {
+        Ok(())
+    } else {
+        Err(ShmemError::MappingIdDoesNotExist)
+    }
+}
+
+//Returns whether a mapping with this uid exists, without creating or mapping it
+pub fn mapping_exists(unique_id: &str) -> bool {
+    let handle = unsafe {
+        OpenFileMappingA(
+            FILE_MAP_READ,
+            FALSE as _,
+            #[allow(clippy::temporary_cstring_as_ptr)]
+            CString::new(unique_id).unwrap().as_ptr(),
+        )
+    };
+    if handle as *mut _ == NULL {
+        //Someone else's mapping
+        return unsafe { GetLastError() } == ERROR_ACCESS_DENIED;
+    }
+    unsafe { CloseHandle(handle) };
+    true
+}
+
 //Opens an existing mapping specified by its uid
-pub fn open_mapping(unique_id: &str) -> Result<MapData, ShmemError> {
+pub fn open_mapping(
+    unique_id: &str,
+    read_only: bool,
+    max_size: Option<usize>,
+) -> Result<MapData, ShmemError> {
+    let map_access = if read_only {
+        FILE_MAP_READ
+    } else {
+        FILE_MAP_READ | FILE_MAP_WRITE
+    };
+
     let mut new_map: MapData = MapData {
         unique_id: String::from(unique_id),
         map_handle: NULL,
+        file_handle: NULL,
         map_size: 0,
+        object_size: 0,
         map_ptr: null_mut(),
+        page_protect: if read_only {
+            PAGE_READONLY
+        } else {
+            PAGE_READWRITE
+        },
+        map_access,
+        security_descriptor: None,
     };
 
     //Open existing mapping
     new_map.map_handle = unsafe {
         OpenFileMappingA(
-            FILE_MAP_READ | FILE_MAP_WRITE,
+            map_access,
             FALSE as _,
             #[allow(clippy::temporary_cstring_as_ptr)]
             CString::new(unique_id).unwrap().as_ptr(),
@@ -114,19 +600,70 @@ pub fn open_mapping(unique_id: &str) -> Result<MapData, ShmemError> {
     };
     if new_map.map_handle as *mut _ == NULL {
         let last_error = unsafe { GetLastError() };
+        debug!(
+            "os_impl::Windows : Failed to OpenFileMappingA({}) : {}",
+            unique_id, last_error
+        );
+        if last_error == ERROR_FILE_NOT_FOUND {
+            return Err(ShmemError::MappingIdDoesNotExist);
+        }
         return Err(ShmemError::MapOpenFailed(last_error));
     }
 
-    //Map mapping into address space
-    new_map.map_ptr =
-        unsafe { MapViewOfFile(new_map.map_handle, FILE_MAP_READ | FILE_MAP_WRITE, 0, 0, 0) } as _;
+    map_existing(&mut new_map, map_access, max_size)?;
+
+    Ok(new_map)
+}
+
+//Opens the mapping referred to by a section handle (e.g. duplicated from another process)
+pub fn from_raw_handle(
+    handle: HANDLE,
+    read_only: bool,
+    max_size: Option<usize>,
+) -> Result<MapData, ShmemError> {
+    let map_access = if read_only {
+        FILE_MAP_READ
+    } else {
+        FILE_MAP_READ | FILE_MAP_WRITE
+    };
+
+    //The handle is closed when dropped
+    let mut new_map: MapData = MapData {
+        unique_id: String::new(),
+        map_handle: handle,
+        file_handle: NULL,
+        map_size: 0,
+        object_size: 0,
+        map_ptr: null_mut(),
+        page_protect: if read_only {
+            PAGE_READONLY
+        } else {
+            PAGE_READWRITE
+        },
+        map_access,
+        security_descriptor: None,
+    };
+
+    map_existing(&mut new_map, map_access, max_size)?;
+
+    Ok(new_map)
+}
+
+//Maps an opened mapping into our address space
+fn map_existing(
+    new_map: &mut MapData,
+    map_access: u32,
+    max_size: Option<usize>,
+) -> Result<(), ShmemError> {
+    //Map the whole section into address space
+    new_map.map_ptr = unsafe { MapViewOfFile(new_map.map_handle, map_access, 0, 0, 0) } as _;
     if new_map.map_ptr.is_null() {
         let last_error = unsafe { GetLastError() };
         return Err(ShmemError::MapOpenFailed(last_error));
     }
 
-    //Get the size of our mapping
-    new_map.map_size = unsafe {
+    //Get the size of the section, the view covers it entirely
+    new_map.object_size = unsafe {
         let mut mem_ba: MEMORY_BASIC_INFORMATION = MEMORY_BASIC_INFORMATION {
             BaseAddress: null_mut(),
             AllocationBase: null_mut(),
@@ -151,5 +688,187 @@ pub fn open_mapping(unique_id: &str) -> Result<MapData, ShmemError> {
         mem_ba.RegionSize
     };
 
+    //Only keep the beginning of the section mapped if requested
+    new_map.map_size = match max_size {
+        Some(v) if v > new_map.object_size => {
+            return Err(ShmemError::MapTooSmall(new_map.object_size, v))
+        }
+        Some(v) => {
+            unsafe { UnmapViewOfFile(new_map.map_ptr as *mut _) };
+            new_map.map_ptr =
+                unsafe { MapViewOfFile(new_map.map_handle, map_access, 0, 0, v) } as _;
+            if new_map.map_ptr.is_null() {
+                let last_error = unsafe { GetLastError() };
+                return Err(ShmemError::MapOpenFailed(last_error));
+            }
+            v
+        }
+        None => new_map.object_size,
+    };
+
+    Ok(())
+}
+
+//Creates an unnamed mapping whose handle can be inherited by child processes
+pub fn create_anonymous_mapping(map_size: usize) -> Result<MapData, ShmemError> {
+    let mut new_map: MapData = MapData {
+        unique_id: String::new(),
+        map_handle: NULL,
+        file_handle: NULL,
+        map_size,
+        object_size: map_size,
+        map_ptr: null_mut(),
+        page_protect: PAGE_READWRITE,
+        map_access: FILE_MAP_READ | FILE_MAP_WRITE,
+        security_descriptor: None,
+    };
+
+    //Create unnamed inheritable mapping
+    let (map_handle, last_error) = create_section(
+        INVALID_HANDLE_VALUE,
+        "",
+        map_size,
+        PAGE_READWRITE,
+        None,
+        true,
+    )?;
+    new_map.map_handle = map_handle;
+    if new_map.map_handle == NULL {
+        return Err(create_error(last_error));
+    }
+
+    //Map mapping into address space
+    new_map.map_ptr =
+        unsafe { MapViewOfFile(new_map.map_handle, FILE_MAP_READ | FILE_MAP_WRITE, 0, 0, 0) } as _;
+    if new_map.map_ptr.is_null() {
+        let last_error = unsafe { GetLastError() };
+        return Err(create_error(last_error));
+    }
+
+    Ok(new_map)
+}
+
+//Creates a mapping backed by a new file on disk
+pub fn create_file_mapping(path: &Path, map_size: usize) -> Result<MapData, ShmemError> {
+    let file = match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(path)
+    {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(ShmemError::MappingIdExists)
+        }
+        Err(e) => return Err(create_error(e.raw_os_error().unwrap_or(0) as u32)),
+    };
+
+    let mut new_map: MapData = MapData {
+        unique_id: path.to_string_lossy().into_owned(),
+        map_handle: NULL,
+        file_handle: file.into_raw_handle() as _,
+        map_size,
+        object_size: map_size,
+        map_ptr: null_mut(),
+        page_protect: PAGE_READWRITE,
+        map_access: FILE_MAP_READ | FILE_MAP_WRITE,
+        security_descriptor: None,
+    };
+
+    //Create unnamed mapping over the file, growing it to the requested size
+    let (map_handle, last_error) = create_section(
+        new_map.file_handle,
+        "",
+        map_size,
+        PAGE_READWRITE,
+        None,
+        false,
+    )?;
+    new_map.map_handle = map_handle;
+    if new_map.map_handle == NULL {
+        //Do not leave an unusable file behind, it can only be removed once closed
+        drop(new_map);
+        let _ = std::fs::remove_file(path);
+        return Err(create_error(last_error));
+    }
+
+    //Map mapping into address space
+    new_map.map_ptr =
+        unsafe { MapViewOfFile(new_map.map_handle, FILE_MAP_READ | FILE_MAP_WRITE, 0, 0, 0) } as _;
+    if new_map.map_ptr.is_null() {
+        let last_error = unsafe { GetLastError() };
+        drop(new_map);
+        let _ = std::fs::remove_file(path);
+        return Err(create_error(last_error));
+    }
+
+    Ok(new_map)
+}
+
+//Opens an existing mapping backed by a file on disk
+pub fn open_file_mapping(
+    path: &Path,
+    read_only: bool,
+    max_size: Option<usize>,
+) -> Result<MapData, ShmemError> {
+    let (page_protect, map_access) = if read_only {
+        (PAGE_READONLY, FILE_MAP_READ)
+    } else {
+        (PAGE_READWRITE, FILE_MAP_READ | FILE_MAP_WRITE)
+    };
+
+    let file = match OpenOptions::new().read(true).write(!read_only).open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ShmemError::MappingIdDoesNotExist)
+        }
+        Err(e) => {
+            return Err(ShmemError::MapOpenFailed(
+                e.raw_os_error().unwrap_or(0) as u32
+            ))
+        }
+    };
+    let object_size = match file.metadata() {
+        Ok(m) => m.len() as usize,
+        Err(e) => {
+            return Err(ShmemError::MapOpenFailed(
+                e.raw_os_error().unwrap_or(0) as u32
+            ))
+        }
+    };
+    //Only map the beginning of the file if requested
+    let map_size = match max_size {
+        Some(v) if v > object_size => return Err(ShmemError::MapTooSmall(object_size, v)),
+        Some(v) => v,
+        None => object_size,
+    };
+
+    let mut new_map: MapData = MapData {
+        unique_id: path.to_string_lossy().into_owned(),
+        map_handle: NULL,
+        file_handle: file.into_raw_handle() as _,
+        map_size,
+        object_size,
+        map_ptr: null_mut(),
+        page_protect,
+        map_access,
+        security_descriptor: None,
+    };
+
+    //Create unnamed mapping over the whole file
+    new_map.map_handle =
+        unsafe { CreateFileMappingA(new_map.file_handle, null_mut(), page_protect, 0, 0, null()) };
+    if new_map.map_handle == NULL {
+        let last_error = unsafe { GetLastError() };
+        return Err(ShmemError::MapOpenFailed(last_error));
+    }
+
+    //Map mapping into address space
+    new_map.map_ptr = unsafe { MapViewOfFile(new_map.map_handle, map_access, 0, 0, map_size) } as _;
+    if new_map.map_ptr.is_null() {
+        let last_error = unsafe { GetLastError() };
+        return Err(ShmemError::MapOpenFailed(last_error));
+    }
+
     Ok(new_map)
 }
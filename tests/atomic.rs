@@ -0,0 +1,31 @@
+use shared_memory::*;
+use std::sync::atomic::Ordering;
+
+#[test]
+fn atomic_counter() {
+    let shmem = ShmemConf::new().size(4096).create().unwrap();
+
+    // Every thread maps the memory separately, like another process would
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let conf = ShmemConf::new().os_id(shmem.get_os_id());
+            std::thread::spawn(move || {
+                let opened = conf.open().unwrap();
+                let counter = unsafe { opened.atomic_u64(8) }.unwrap();
+                for _ in 0..1000 {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    let counter = unsafe { shmem.atomic_u64(8) }.unwrap();
+    assert_eq!(counter.load(Ordering::SeqCst), 4000);
+    assert!(matches!(
+        unsafe { shmem.atomic_u64(4) },
+        Err(ShmemError::AlignmentError)
+    ));
+}
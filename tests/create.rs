@@ -0,0 +1,317 @@
+use shared_memory::*;
+
+#[test]
+fn size_is_not_rounded() {
+    let shmem = ShmemConf::new().size(100).create().unwrap();
+    assert_eq!(shmem.len(), 100);
+    assert_eq!(shmem.mapped_size(), page_size());
+
+    // Every process sees the same range through as_slice()
+    let opened = ShmemConf::new().os_id(shmem.get_os_id()).open().unwrap();
+    #[cfg(not(target_os = "windows"))]
+    assert_eq!(opened.len(), 100);
+    assert_eq!(opened.mapped_size(), page_size());
+}
+
+#[test]
+fn backing_file_removed_on_failure() {
+    let path = std::env::temp_dir().join(format!("shmem_backing_{}", std::process::id()));
+    let occupied = ShmemConf::new().size(4096).create().unwrap();
+
+    // The mapping cannot be placed, the backing file must not be left behind
+    let res = ShmemConf::new()
+        .size(4096)
+        .backing_file(&path)
+        .desired_base(occupied.as_ptr() as usize)
+        .create();
+    assert!(matches!(res, Err(ShmemError::BaseAddressUnavailable)));
+    assert!(!path.exists());
+
+    let shmem = ShmemConf::new()
+        .size(4096)
+        .backing_file(&path)
+        .create()
+        .unwrap();
+    drop(shmem);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn backing_file_rejects_flinks() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("shmem_backing_flinked_{}", std::process::id()));
+    let flink = dir.join(format!(
+        "shmem_backing_flinked_flink_{}",
+        std::process::id()
+    ));
+    let res = ShmemConf::new()
+        .size(4096)
+        .backing_file(&path)
+        .flink(&flink)
+        .create();
+    assert!(matches!(res, Err(ShmemError::LinkUnsupported)));
+    assert!(!path.exists());
+    assert!(!flink.exists());
+}
+
+#[test]
+fn name_generator_retries() {
+    let pid = std::process::id();
+    let taken = ShmemConf::new()
+        .size(4096)
+        .os_id(format!("/shmem_gen_{}_0", pid))
+        .create()
+        .unwrap();
+
+    // The first generated name is taken, the generator is asked for another one
+    let mut n = 0;
+    let shmem = ShmemConf::new()
+        .size(4096)
+        .os_id_prefix("/shmem_")
+        .name_generator(move || {
+            n += 1;
+            format!("gen_{}_{}", pid, n - 1)
+        })
+        .create()
+        .unwrap();
+    assert_eq!(shmem.get_os_id(), format!("/shmem_gen_{}_1", pid));
+    drop(taken);
+
+    // A long prefix is fine as long as the generated names are short
+    let prefix = format!("/{}", "x".repeat(250));
+    let res = ShmemConf::new().size(4096).os_id_prefix(&prefix).validate();
+    assert!(matches!(res, Err(ShmemError::OsIdTooLong)));
+    let res = ShmemConf::new()
+        .size(4096)
+        .os_id_prefix(&prefix)
+        .name_generator(|| String::from("x"))
+        .validate();
+    assert!(res.is_ok());
+}
+
+#[test]
+fn name_generator_kept_by_open_or_create() {
+    let pid = std::process::id();
+    let flink = std::env::temp_dir().join(format!("shmem_gen_flink_{}", pid));
+    // An empty flink looks like a creator that has not written it yet
+    std::fs::write(&flink, "").unwrap();
+    let remover_flink = flink.clone();
+    let remover = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::remove_file(&remover_flink).unwrap();
+    });
+
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let generator_calls = calls.clone();
+    let (shmem, created) = ShmemConf::new()
+        .size(4096)
+        .flink(&flink)
+        .name_generator(move || {
+            let n = generator_calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            format!("gen_{}_retry_{}", pid, n)
+        })
+        .open_or_create()
+        .unwrap();
+    remover.join().unwrap();
+    assert!(created);
+    assert!(calls.load(std::sync::atomic::Ordering::SeqCst) > 1);
+    assert!(shmem.get_os_id().contains(&format!("gen_{}_retry_", pid)));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn memfd_rejects_flinks() {
+    let flink = std::env::temp_dir().join(format!("shmem_memfd_flink_{}", std::process::id()));
+    let res = ShmemConf::new()
+        .size(4096)
+        .use_memfd()
+        .flink(&flink)
+        .create();
+    assert!(matches!(res, Err(ShmemError::LinkUnsupported)));
+    assert!(!flink.exists());
+
+    let shmem = ShmemConf::new().size(4096).use_memfd().create().unwrap();
+    assert!(matches!(
+        shmem.relink(&flink),
+        Err(ShmemError::LinkUnsupported)
+    ));
+    assert!(!flink.exists());
+}
+
+#[test]
+fn backing_file_rejects_relink() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("shmem_relink_backing_{}", std::process::id()));
+    let flink = dir.join(format!("shmem_relink_backing_flink_{}", std::process::id()));
+    let shmem = ShmemConf::new()
+        .size(4096)
+        .backing_file(&path)
+        .create()
+        .unwrap();
+    assert!(matches!(
+        shmem.relink(&flink),
+        Err(ShmemError::LinkUnsupported)
+    ));
+    assert!(!flink.exists());
+    drop(shmem);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn generated_os_id_collision() {
+    let taken = ShmemConf::new().size(4096).create().unwrap();
+
+    // A fresh random os_id is used next to an existing mapping
+    let shmem = ShmemConf::new().size(4096).create().unwrap();
+    assert_ne!(shmem.get_os_id(), taken.get_os_id());
+
+    // Colliding names are only retried a bounded number of times
+    let taken_name = String::from(taken.get_os_id().rsplit("/shmem_").next().unwrap());
+    let res = ShmemConf::new()
+        .size(4096)
+        .name_generator(move || taken_name.clone())
+        .create();
+    assert!(matches!(res, Err(ShmemError::MappingIdExists)));
+
+    // An explicit os_id is never replaced
+    let res = ShmemConf::new()
+        .size(4096)
+        .os_id(taken.get_os_id())
+        .create();
+    assert!(matches!(res, Err(ShmemError::MappingIdExists)));
+}
+
+#[cfg(unix)]
+#[test]
+fn anonymous_shared_with_fork() {
+    let mut shmem = ShmemConf::new().size(4096).create_anonymous().unwrap();
+
+    // The child writes into the mapping it inherited
+    match unsafe { libc::fork() } {
+        0 => unsafe {
+            let _ = shmem.write_at(0, &42u8);
+            libc::_exit(0);
+        },
+        pid => {
+            assert!(pid > 0);
+            let mut status = 0;
+            unsafe { libc::waitpid(pid, &mut status, 0) };
+            assert_eq!(status, 0);
+        }
+    }
+    assert_eq!(unsafe { shmem.read_at::<u8>(0) }.unwrap(), 42);
+
+    assert!(matches!(
+        shmem.grow(4096),
+        Err(ShmemError::ResizeUnsupported)
+    ));
+}
+
+#[test]
+fn anonymous_rejects_unsupported_options() {
+    let flink = std::env::temp_dir().join(format!("shmem_anonymous_flink_{}", std::process::id()));
+    let res = ShmemConf::new().size(4096).flink(&flink).create_anonymous();
+    assert!(matches!(res, Err(ShmemError::LinkUnsupported)));
+    assert!(!flink.exists());
+
+    let res = ShmemConf::new().size(4096).read_only().create_anonymous();
+    assert!(matches!(res, Err(ShmemError::WriteOnReadOnly)));
+}
+
+#[test]
+fn close_reports_success() {
+    let flink = std::env::temp_dir().join(format!("shmem_close_{}", std::process::id()));
+    let shmem = ShmemConf::new().size(4096).flink(&flink).create().unwrap();
+    let os_id = shmem.get_os_id().to_string();
+    let opened = ShmemConf::new().flink(&flink).open().unwrap();
+
+    assert!(opened.close().is_ok());
+    assert!(shmem.close().is_ok());
+    assert!(!flink.exists());
+    #[cfg(not(target_os = "windows"))]
+    assert!(!ShmemConf::os_id_exists(&os_id));
+
+    // The Drop that follows close() must not release anything again, which would hit this new mapping
+    let mut shmem = ShmemConf::new().size(4096).os_id(&os_id).create().unwrap();
+    unsafe { shmem.as_slice_mut().unwrap()[0] = 42 };
+    let opened = ShmemConf::new().os_id(&os_id).open().unwrap();
+    assert_eq!(unsafe { opened.as_slice() }[0], 42);
+}
+
+#[cfg(unix)]
+#[test]
+fn force_create_mapping() {
+    let os_id = format!("/shmem_force_create_{}", std::process::id());
+    let mut stale = ShmemConf::new().size(4096).os_id(&os_id).create().unwrap();
+    stale.set_owner(false);
+    unsafe { stale.as_slice_mut().unwrap()[0] = 42 };
+
+    let res = ShmemConf::new().size(4096).os_id(&os_id).create();
+    assert!(matches!(res, Err(ShmemError::MappingIdExists)));
+
+    // The stale mapping is replaced by a fresh one, its user keeps the old memory
+    let shmem = ShmemConf::new()
+        .size(8192)
+        .os_id(&os_id)
+        .force_create_mapping()
+        .create()
+        .unwrap();
+    let opened = ShmemConf::new().os_id(&os_id).open().unwrap();
+    assert_eq!(opened.len(), 8192);
+    assert_eq!(unsafe { opened.as_slice() }[0], 0);
+    assert_eq!(unsafe { stale.as_slice() }[0], 42);
+    drop(shmem);
+}
+
+#[test]
+fn list_mappings() {
+    let prefix = format!("/shmem_list_{}_", std::process::id());
+    let a = ShmemConf::new()
+        .size(4096)
+        .os_id_prefix(&prefix)
+        .create()
+        .unwrap();
+    let b = ShmemConf::new()
+        .size(4096)
+        .os_id_prefix(&prefix)
+        .create()
+        .unwrap();
+
+    match shared_memory::list_mappings(&prefix) {
+        Ok(mut os_ids) => {
+            os_ids.sort();
+            let mut expected = vec![a.get_os_id().to_string(), b.get_os_id().to_string()];
+            expected.sort();
+            assert_eq!(os_ids, expected);
+        }
+        #[cfg(not(target_os = "linux"))]
+        Err(ShmemError::ListUnsupported) => {}
+        Err(e) => panic!("{:?}", e),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let shmem = ShmemConf::new().size(4096).mode(0o640).create().unwrap();
+    let path = format!("/dev/shm{}", shmem.get_os_id());
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+
+    // Only the current user can open the mapping by default
+    let shmem = ShmemConf::new().size(4096).create().unwrap();
+    let path = format!("/dev/shm{}", shmem.get_os_id());
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o077, 0);
+}
+
+#[test]
+fn size_too_large() {
+    for size in [usize::MAX, isize::MAX as usize + 1].iter() {
+        let conf = ShmemConf::new().size(*size);
+        assert!(matches!(conf.validate(), Err(ShmemError::MapSizeTooLarge)));
+        assert!(matches!(conf.create(), Err(ShmemError::MapSizeTooLarge)));
+    }
+}
@@ -0,0 +1,145 @@
+use shared_memory::*;
+
+#[test]
+fn relink_is_atomic() {
+    let flink = std::env::temp_dir().join(format!("shmem_relink_{}", std::process::id()));
+    let a = ShmemConf::new().size(4096).flink(&flink).create().unwrap();
+    let b = ShmemConf::new().size(4096).create().unwrap();
+    let os_ids = [a.get_os_id().to_string(), b.get_os_id().to_string()];
+
+    // Both mappings are relinked concurrently while a reader keeps opening the flink
+    let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let reader_stop = stop.clone();
+    let reader_flink = flink.clone();
+    let reader = std::thread::spawn(move || {
+        let mut opened = 0;
+        while !reader_stop.load(std::sync::atomic::Ordering::SeqCst) || opened == 0 {
+            let shmem = ShmemConf::new().flink(&reader_flink).open().unwrap();
+            assert!(os_ids.iter().any(|id| id == shmem.get_os_id()));
+            opened += 1;
+        }
+    });
+    let relinkers: Vec<_> = vec![a, b]
+        .into_iter()
+        .map(|shmem| {
+            let flink = flink.clone();
+            std::thread::spawn(move || {
+                for _ in 0..200 {
+                    shmem.relink(&flink).unwrap();
+                }
+                shmem
+            })
+        })
+        .collect();
+
+    let owners: Vec<Shmem> = relinkers.into_iter().map(|t| t.join().unwrap()).collect();
+    stop.store(true, std::sync::atomic::Ordering::SeqCst);
+    reader.join().unwrap();
+
+    // No temporary file is left behind
+    let dir = std::fs::read_dir(std::env::temp_dir()).unwrap();
+    let prefix = format!("shmem_relink_{}.", std::process::id());
+    assert!(!dir
+        .map(|e| e.unwrap().file_name())
+        .any(|name| name.to_string_lossy().starts_with(&prefix)));
+    drop(owners);
+}
+
+#[test]
+fn create_flink_dirs() {
+    let dir = std::env::temp_dir().join(format!("shmem_flink_dirs_{}", std::process::id()));
+    let flink = dir.join("nested").join("flink");
+
+    let res = ShmemConf::new().size(4096).flink(&flink).create();
+    assert!(matches!(res, Err(ShmemError::LinkDirMissing(ref d)) if *d == dir.join("nested")));
+
+    let shmem = ShmemConf::new()
+        .size(4096)
+        .flink(&flink)
+        .create_flink_dirs()
+        .create()
+        .unwrap();
+    assert!(flink.exists());
+    drop(shmem);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn add_flink() {
+    let dir = std::env::temp_dir();
+    let pid = std::process::id();
+    let flinks: Vec<_> = (0..3)
+        .map(|i| dir.join(format!("shmem_add_flink_{}_{}", pid, i)))
+        .collect();
+
+    // The mapping can be opened through each of its flinks
+    let shmem = ShmemConf::new()
+        .size(4096)
+        .flink(&flinks[0])
+        .add_flink(&flinks[1])
+        .add_flink(&flinks[2])
+        .create()
+        .unwrap();
+    for flink in &flinks {
+        let opened = ShmemConf::new().flink(flink).open().unwrap();
+        assert_eq!(opened.get_os_id(), shmem.get_os_id());
+    }
+    drop(shmem);
+    assert!(flinks.iter().all(|f| !f.exists()));
+
+    // The flinks already written are removed when a later one cannot be created
+    std::fs::write(&flinks[2], "taken").unwrap();
+    let res = ShmemConf::new()
+        .size(4096)
+        .flink(&flinks[0])
+        .add_flink(&flinks[1])
+        .add_flink(&flinks[2])
+        .create();
+    assert!(matches!(res, Err(ShmemError::LinkExists)));
+    assert!(!flinks[0].exists());
+    assert!(!flinks[1].exists());
+    assert_eq!(std::fs::read_to_string(&flinks[2]).unwrap(), "taken");
+    std::fs::remove_file(&flinks[2]).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn force_remove_after_crash() {
+    let pid = std::process::id();
+    let flink = std::env::temp_dir().join(format!("shmem_force_remove_{}", pid));
+    let os_id = format!("/shmem_force_remove_{}", pid);
+    let conf = || ShmemConf::new().size(4096).os_id(&os_id).flink(&flink);
+
+    // An owner that never cleaned up leaves its os_id and flink behind
+    let mut shmem = conf().create().unwrap();
+    shmem.set_owner(false);
+    drop(shmem);
+    assert!(matches!(conf().create(), Err(ShmemError::MappingIdExists)));
+
+    force_remove_flink(&flink).unwrap();
+    force_remove(&os_id).unwrap();
+    assert!(matches!(
+        force_remove_flink(&flink),
+        Err(ShmemError::LinkDoesNotExist)
+    ));
+    assert!(matches!(
+        force_remove(&os_id),
+        Err(ShmemError::MappingIdDoesNotExist)
+    ));
+
+    let shmem = conf().create().unwrap();
+    assert_eq!(shmem.get_os_id(), os_id);
+}
+
+#[test]
+fn exists_while_owner_alive() {
+    let flink = std::env::temp_dir().join(format!("shmem_exists_{}", std::process::id()));
+    let shmem = ShmemConf::new().size(4096).flink(&flink).create().unwrap();
+    let os_id = shmem.get_os_id().to_string();
+    assert!(ShmemConf::os_id_exists(&os_id));
+    assert!(ShmemConf::flink_exists(&flink));
+
+    drop(shmem);
+    assert!(!ShmemConf::os_id_exists(&os_id));
+    assert!(!ShmemConf::flink_exists(&flink));
+}
@@ -0,0 +1,63 @@
+use shared_memory::*;
+
+#[test]
+fn grow_keeps_content() {
+    let mut shmem = ShmemConf::new().size(4096).create().unwrap();
+    unsafe { shmem.as_slice_mut().unwrap()[4095] = 42 };
+
+    let ptr = shmem.grow(4096).unwrap();
+    assert_eq!(ptr, shmem.as_ptr());
+    assert_eq!(shmem.len(), 8192);
+    assert_eq!(unsafe { shmem.as_slice() }[4095], 42);
+    unsafe { shmem.as_slice_mut().unwrap()[8191] = 42 };
+
+    // Other processes see the new size once they open the mapping again
+    let opened = ShmemConf::new().os_id(shmem.get_os_id()).open().unwrap();
+    assert_eq!(opened.len(), 8192);
+    assert_eq!(unsafe { opened.as_slice() }[8191], 42);
+}
+
+#[test]
+fn grow_keeps_desired_base() {
+    let base = {
+        let shmem = ShmemConf::new().size(4096).create().unwrap();
+        shmem.as_ptr() as usize
+    };
+    let mut shmem = ShmemConf::new()
+        .size(4096)
+        .desired_base(base)
+        .create()
+        .unwrap();
+    if shmem.as_ptr() as usize != base {
+        return;
+    }
+
+    // The mapping is either extended in place or left untouched
+    match shmem.grow(1 << 20) {
+        Ok(ptr) => assert_eq!(ptr as usize, base),
+        Err(ShmemError::BaseAddressUnavailable) => assert_eq!(shmem.len(), 4096),
+        Err(e) => panic!("{:?}", e),
+    }
+    assert_eq!(shmem.as_ptr() as usize, base);
+}
+
+#[test]
+fn grow_rejects_subset() {
+    let shmem = ShmemConf::new().size(1 << 20).create().unwrap();
+    let mut subset = ShmemConf::new()
+        .os_id(shmem.get_os_id())
+        .map_subset(100)
+        .open()
+        .unwrap();
+
+    // Growing from the mapped part would truncate the object under every other process
+    subset.set_owner(true);
+    assert!(matches!(
+        subset.grow(100),
+        Err(ShmemError::ResizeUnsupported)
+    ));
+    subset.set_owner(false);
+
+    let opened = ShmemConf::new().os_id(shmem.get_os_id()).open().unwrap();
+    assert_eq!(opened.len(), 1 << 20);
+}
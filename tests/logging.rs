@@ -0,0 +1,50 @@
+#![cfg(feature = "logging")]
+
+use shared_memory::*;
+use std::sync::Mutex;
+
+struct CaptureLogger {
+    records: Mutex<Vec<String>>,
+}
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.target().starts_with("shared_memory") {
+            self.records
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn create_and_open_are_logged() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let shmem = ShmemConf::new().size(4096).create().unwrap();
+    let os_id = shmem.get_os_id().to_string();
+    let opened = ShmemConf::new().os_id(&os_id).open().unwrap();
+    drop(opened);
+    drop(shmem);
+
+    let records = LOGGER.records.lock().unwrap();
+    let logged = |prefix: &str| {
+        records
+            .iter()
+            .any(|r| r.starts_with(prefix) && r.contains(&os_id))
+    };
+    assert!(logged("DEBUG Created mapping"), "{:?}", records);
+    assert!(logged("DEBUG Opened mapping"), "{:?}", records);
+}
@@ -0,0 +1,377 @@
+use shared_memory::*;
+
+#[cfg(not(target_os = "windows"))]
+#[test]
+fn require_min_size() {
+    let shmem = ShmemConf::new().size(100).create().unwrap();
+    let res = ShmemConf::new()
+        .os_id(shmem.get_os_id())
+        .require_min_size(200)
+        .open();
+    assert!(matches!(res, Err(ShmemError::MapTooSmall(100, 200))));
+    ShmemConf::new()
+        .os_id(shmem.get_os_id())
+        .require_min_size(100)
+        .open()
+        .unwrap();
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn require_min_size_from_json_flink() {
+    let flink = std::env::temp_dir().join(format!("shmem_min_size_{}", std::process::id()));
+    let shmem = ShmemConf::new()
+        .size(100)
+        .flink(&flink)
+        .flink_format(FlinkFormat::Json)
+        .create()
+        .unwrap();
+    let res = ShmemConf::new().flink(&flink).require_min_size(200).open();
+    assert!(matches!(res, Err(ShmemError::MapTooSmall(100, 200))));
+    let opened = ShmemConf::new().flink(&flink).open().unwrap();
+    assert_eq!(opened.len(), shmem.len());
+}
+
+#[test]
+fn map_subset() {
+    let shmem = ShmemConf::new().size(1 << 20).create().unwrap();
+    let opened = ShmemConf::new()
+        .os_id(shmem.get_os_id())
+        .map_subset(100)
+        .require_min_size(1 << 20)
+        .open()
+        .unwrap();
+    assert_eq!(opened.len(), 100);
+    assert_eq!(opened.mapped_size(), page_size());
+
+    let res = ShmemConf::new()
+        .os_id(shmem.get_os_id())
+        .map_subset(2 << 20)
+        .open();
+    assert!(matches!(res, Err(ShmemError::MapTooSmall(_, _))));
+}
+
+#[test]
+fn open_blocking_waits_for_creator() {
+    let flink = std::env::temp_dir().join(format!("shmem_blocking_{}", std::process::id()));
+    let creator_flink = flink.clone();
+    let creator = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let shmem = ShmemConf::new()
+            .size(4096)
+            .flink(&creator_flink)
+            .create()
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        drop(shmem);
+    });
+
+    let opened = ShmemConf::new()
+        .flink(&flink)
+        .open_blocking(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(opened.len(), 4096);
+    creator.join().unwrap();
+}
+
+#[test]
+fn open_blocking_waits_for_backing_file() {
+    let path = std::env::temp_dir().join(format!("shmem_blocking_file_{}", std::process::id()));
+    let res = ShmemConf::new()
+        .backing_file(&path)
+        .open_blocking(Some(std::time::Duration::from_millis(20)));
+    assert!(matches!(res, Err(ShmemError::Timeout)));
+
+    let creator_path = path.clone();
+    let creator = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let shmem = ShmemConf::new()
+            .size(4096)
+            .backing_file(&creator_path)
+            .create()
+            .unwrap();
+        drop(shmem);
+    });
+
+    let opened = ShmemConf::new()
+        .backing_file(&path)
+        .open_blocking(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(opened.len(), 4096);
+    creator.join().unwrap();
+    drop(opened);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_blocking_fails_on_corrupt_flink() {
+    let flink = std::env::temp_dir().join(format!("shmem_blocking_corrupt_{}", std::process::id()));
+    std::fs::write(&flink, b"\xff\xfe").unwrap();
+    let res = ShmemConf::new().flink(&flink).open_blocking(None);
+    assert!(matches!(res, Err(ShmemError::LinkCorrupt)));
+    std::fs::remove_file(&flink).unwrap();
+}
+
+/// Runs `open_or_create()` on the flink passed by `open_or_create_race` when spawned by it
+#[test]
+fn open_or_create_race_child() {
+    let flink = match std::env::var_os("SHMEM_RACE_FLINK") {
+        Some(flink) => flink,
+        None => return,
+    };
+    // Forcing the creation must not let every peer create the mapping
+    let (shmem, created) = ShmemConf::new()
+        .size(4096)
+        .flink(flink)
+        .force_create_flink()
+        .open_or_create()
+        .unwrap();
+    println!("created={}", created);
+    // Keep the mapping alive until the parent closes our stdin, once every peer opened it
+    std::io::Read::read_to_end(&mut std::io::stdin(), &mut Vec::new()).unwrap();
+    drop(shmem);
+}
+
+#[test]
+fn open_or_create_race() {
+    use std::io::BufRead;
+
+    let flink = std::env::temp_dir().join(format!("shmem_race_{}", std::process::id()));
+    let exe = std::env::current_exe().unwrap();
+    let mut children: Vec<_> = (0..4)
+        .map(|_| {
+            std::process::Command::new(&exe)
+                .args(["--exact", "open_or_create_race_child", "--nocapture"])
+                .env("SHMEM_RACE_FLINK", &flink)
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+
+    // Every child reports before any of them releases its mapping
+    let mut created = 0;
+    for child in &mut children {
+        let stdout = std::io::BufReader::new(child.stdout.as_mut().unwrap());
+        let line = stdout
+            .lines()
+            .map(|l| l.unwrap())
+            .find(|l| l.contains("created="))
+            .unwrap();
+        // libtest prints the name of the test on the same line
+        if line.ends_with("created=true") {
+            created += 1;
+        } else {
+            assert!(line.ends_with("created=false"));
+        }
+    }
+    for mut child in children {
+        drop(child.stdin.take());
+        assert!(child.wait().unwrap().success());
+    }
+    assert_eq!(created, 1);
+}
+
+#[test]
+fn flink_contents_are_validated() {
+    let shmem = ShmemConf::new().size(4096).create().unwrap();
+    let flink = std::env::temp_dir().join(format!("shmem_flink_parse_{}", std::process::id()));
+
+    // Binary or empty flinks are reported instead of panicking
+    std::fs::write(&flink, b"\xff\xfe").unwrap();
+    let res = ShmemConf::new().flink(&flink).open();
+    assert!(matches!(res, Err(ShmemError::LinkCorrupt)));
+    std::fs::write(&flink, b"").unwrap();
+    let res = ShmemConf::new().flink(&flink).open();
+    assert!(matches!(res, Err(ShmemError::LinkCorrupt)));
+
+    // Editors may append a newline
+    std::fs::write(&flink, format!("{}\n", shmem.get_os_id())).unwrap();
+    let opened = ShmemConf::new().flink(&flink).open().unwrap();
+    assert_eq!(opened.get_os_id(), shmem.get_os_id());
+
+    std::fs::remove_file(&flink).unwrap();
+}
+
+#[test]
+fn read_only() {
+    let flink = std::env::temp_dir().join(format!("shmem_read_only_{}", std::process::id()));
+    let mut shmem = ShmemConf::new().size(4096).flink(&flink).create().unwrap();
+    unsafe { shmem.as_slice_mut().unwrap()[0] = 42 };
+
+    // A read only peer never creates the mapping and opens it instead
+    let (mut opened, created) = ShmemConf::new()
+        .flink(&flink)
+        .read_only()
+        .open_or_create()
+        .unwrap();
+    assert!(!created);
+    assert!(opened.is_read_only());
+    assert_eq!(unsafe { opened.as_slice() }[0], 42);
+
+    // Every mutable accessor reports the same error
+    assert!(matches!(
+        unsafe { opened.as_slice_mut() },
+        Err(ShmemError::WriteOnReadOnly)
+    ));
+    assert!(matches!(
+        unsafe { opened.as_typed_slice_mut::<u32>() },
+        Err(ShmemError::WriteOnReadOnly)
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn into_raw_fd_and_open_fd() {
+    use std::os::unix::io::IntoRawFd;
+
+    let mut shmem = ShmemConf::new().size(4096).create().unwrap();
+    unsafe { shmem.as_slice_mut().unwrap()[0] = 42 };
+    let os_id = shmem.get_os_id().to_string();
+
+    // The fd keeps the object alive after the Shmem is gone
+    let fd = shmem.into_raw_fd();
+    let opened = ShmemConf::new().open_fd(fd).unwrap();
+    assert_eq!(opened.len(), 4096);
+    assert_eq!(unsafe { opened.as_slice() }[0], 42);
+    drop(opened);
+
+    // Ownership of the os_id was handed over as well
+    force_remove(&os_id).unwrap();
+}
+
+#[cfg(target_os = "windows")]
+#[test]
+fn into_raw_handle_and_open_handle() {
+    use std::os::windows::io::IntoRawHandle;
+
+    let mut shmem = ShmemConf::new().size(4096).create().unwrap();
+    unsafe { shmem.as_slice_mut().unwrap()[0] = 42 };
+
+    // The handle keeps the section alive after the Shmem is gone
+    let handle = shmem.into_raw_handle();
+    let opened = ShmemConf::new().open_handle(handle).unwrap();
+    assert_eq!(unsafe { opened.as_slice() }[0], 42);
+}
+
+#[test]
+fn open_strategy() {
+    let dir = std::env::temp_dir();
+    let flink = dir.join(format!("shmem_strategy_{}", std::process::id()));
+    let missing_flink = dir.join(format!("shmem_strategy_missing_{}", std::process::id()));
+    let missing_os_id = format!("/shmem_strategy_missing_{}", std::process::id());
+    let linked = ShmemConf::new().size(4096).flink(&flink).create().unwrap();
+    let named = ShmemConf::new().size(4096).create().unwrap();
+
+    let open = |strategy, os_id: &str, flink: &std::path::Path| {
+        ShmemConf::new()
+            .os_id(os_id)
+            .flink(flink)
+            .open_strategy(strategy)
+            .open()
+    };
+
+    let opened = open(OpenStrategy::OsIdOnly, named.get_os_id(), &flink).unwrap();
+    assert_eq!(opened.get_os_id(), named.get_os_id());
+    let opened = open(OpenStrategy::FlinkOnly, named.get_os_id(), &flink).unwrap();
+    assert_eq!(opened.get_os_id(), linked.get_os_id());
+
+    // The first source wins when it works, the second one is used otherwise
+    let opened = open(OpenStrategy::OsIdThenFlink, named.get_os_id(), &flink).unwrap();
+    assert_eq!(opened.get_os_id(), named.get_os_id());
+    let opened = open(OpenStrategy::OsIdThenFlink, &missing_os_id, &flink).unwrap();
+    assert_eq!(opened.get_os_id(), linked.get_os_id());
+    let opened = open(OpenStrategy::FlinkThenOsId, named.get_os_id(), &flink).unwrap();
+    assert_eq!(opened.get_os_id(), linked.get_os_id());
+    let opened = open(
+        OpenStrategy::FlinkThenOsId,
+        named.get_os_id(),
+        &missing_flink,
+    )
+    .unwrap();
+    assert_eq!(opened.get_os_id(), named.get_os_id());
+
+    // Without fallback, or when both sources fail, the error of the last source is returned
+    let res = open(OpenStrategy::OsIdOnly, &missing_os_id, &flink);
+    assert!(matches!(res, Err(ShmemError::MappingIdDoesNotExist)));
+    let res = open(OpenStrategy::FlinkOnly, named.get_os_id(), &missing_flink);
+    assert!(matches!(res, Err(ShmemError::LinkOpenFailed(_))));
+    let res = open(OpenStrategy::OsIdThenFlink, &missing_os_id, &missing_flink);
+    assert!(matches!(res, Err(ShmemError::LinkOpenFailed(_))));
+    let res = open(OpenStrategy::FlinkThenOsId, &missing_os_id, &missing_flink);
+    assert!(matches!(res, Err(ShmemError::MappingIdDoesNotExist)));
+}
+
+#[test]
+fn on_open_rejects_mapping() {
+    let mut shmem = ShmemConf::new().size(4096).create().unwrap();
+    unsafe { shmem.as_slice_mut().unwrap()[0] = 1 };
+
+    // The hook sees the mapping and rejects its version
+    let mapped_at = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let hook_mapped_at = mapped_at.clone();
+    let res = ShmemConf::new()
+        .os_id(shmem.get_os_id())
+        .on_open(move |opened| {
+            hook_mapped_at.store(
+                opened.as_ptr() as usize,
+                std::sync::atomic::Ordering::SeqCst,
+            );
+            match unsafe { opened.as_slice() }[0] {
+                2 => Ok(()),
+                _ => Err(ShmemError::AlignmentError),
+            }
+        })
+        .open();
+    assert!(matches!(res, Err(ShmemError::AlignmentError)));
+    let mapped_at = mapped_at.load(std::sync::atomic::Ordering::SeqCst);
+    assert_ne!(mapped_at, 0);
+
+    // Nothing is left mapped at the address the hook saw
+    #[cfg(target_os = "linux")]
+    {
+        let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+        let start = format!("{:x}-", mapped_at);
+        let name = shmem.get_os_id().trim_start_matches('/');
+        assert!(!maps
+            .lines()
+            .any(|l| l.starts_with(&start) && l.contains(name)));
+    }
+
+    // The hook accepts the mapping once it is migrated
+    unsafe { shmem.as_slice_mut().unwrap()[0] = 2 };
+    let res = ShmemConf::new()
+        .os_id(shmem.get_os_id())
+        .on_open(|opened| match unsafe { opened.as_slice() }[0] {
+            2 => Ok(()),
+            _ => Err(ShmemError::AlignmentError),
+        })
+        .open();
+    assert!(res.is_ok());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn memfd_open_fd() {
+    use std::os::unix::io::AsRawFd;
+
+    let mut shmem = ShmemConf::new().size(4096).use_memfd().create().unwrap();
+    unsafe { shmem.as_slice_mut().unwrap()[0] = 42 };
+
+    // A duplicate of the fd is what would be sent to another process
+    let fd = unsafe { libc::dup(shmem.as_raw_fd()) };
+    assert!(fd >= 0);
+    let mut opened = ShmemConf::new().open_fd(fd).unwrap();
+    assert!(!opened.is_owner());
+    assert_eq!(opened.len(), 4096);
+    assert_eq!(unsafe { opened.as_slice() }[0], 42);
+
+    // Both mappings share the same memory
+    unsafe { opened.as_slice_mut().unwrap()[1] = 24 };
+    assert_eq!(unsafe { shmem.as_slice() }[1], 24);
+
+    // The memory outlives the creator while the fd is open
+    drop(shmem);
+    assert_eq!(unsafe { opened.as_slice() }[0], 42);
+}